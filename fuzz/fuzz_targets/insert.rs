@@ -0,0 +1,180 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use packr2::*;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    max_width: u16,
+    max_height: u16,
+    allow_flipping: bool,
+    padding: u8,
+    border: u8,
+    extrude: u8,
+    packer: u8,
+    /// Picks the heuristic and the other options of the packer.
+    options: u8,
+    ops: Vec<Op>,
+}
+
+const SKYLINE_HEURISTICS: [SkylineHeuristic; 2] =
+    [SkylineHeuristic::BottomLeft, SkylineHeuristic::MinWaste];
+
+const FREE_RECT_CHOICES: [FreeRectChoice; 6] = [
+    FreeRectChoice::BestAreaFit,
+    FreeRectChoice::BestShortSideFit,
+    FreeRectChoice::BestLongSideFit,
+    FreeRectChoice::WorstAreaFit,
+    FreeRectChoice::WorstShortSideFit,
+    FreeRectChoice::WorstLongSideFit,
+];
+
+const SPLIT_RULES: [SplitRule; 6] = [
+    SplitRule::ShorterLeftoverAxis,
+    SplitRule::LongerLeftoverAxis,
+    SplitRule::MinimizeArea,
+    SplitRule::MaximizeArea,
+    SplitRule::ShorterAxis,
+    SplitRule::LongerAxis,
+];
+
+const MAX_RECTS_HEURISTICS: [MaxRectsHeuristic; 5] = [
+    MaxRectsHeuristic::BestShortSideFit,
+    MaxRectsHeuristic::BestLongSideFit,
+    MaxRectsHeuristic::BestAreaFit,
+    MaxRectsHeuristic::BottomLeft,
+    MaxRectsHeuristic::ContactPoint,
+];
+
+const SHELF_CHOICES: [ShelfChoice; 6] = [
+    ShelfChoice::NextFit,
+    ShelfChoice::FirstFit,
+    ShelfChoice::BestWidthFit,
+    ShelfChoice::BestHeightFit,
+    ShelfChoice::BestAreaFit,
+    ShelfChoice::WorstWidthFit,
+];
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Insert(u8, u8),
+    /// Any size, mostly too big to fit, to check the overflows.
+    InsertAny(u32, u32),
+    Remove(u8),
+    Grow(u8, u8),
+}
+
+/// Runs the `ops` on the `packer`, `remove` is `None` for the packers that can only insert.
+fn run<P: Packer>(
+    config: PackerConfig,
+    mut packer: P,
+    ops: &[Op],
+    remove: Option<fn(&mut P, &Rectf)>,
+) {
+    let mut size = Size::new(config.max_width, config.max_height);
+    let margin = config.border + config.extrude;
+    let gap = config.padding + 2 * config.extrude;
+
+    let mut live: Vec<Rectf> = vec![];
+    for op in ops {
+        let (w, h) = match *op {
+            Op::Insert(w, h) => (w as u32, h as u32),
+            Op::InsertAny(w, h) => (w, h),
+            Op::Remove(i) => {
+                if let (Some(remove), false) = (remove, live.is_empty()) {
+                    let rect = live.swap_remove(i as usize % live.len());
+                    remove(&mut packer, &rect);
+                }
+                continue;
+            }
+            Op::Grow(w, h) => {
+                size.w += w as u32;
+                size.h += h as u32;
+                packer.grow(size);
+                continue;
+            }
+        };
+
+        let Some(rect) = packer.insert(w, h) else {
+            continue;
+        };
+        assert!(w > 0 && h > 0, "empty {rect:?} placed");
+
+        let expected = if rect.flipped { (h, w) } else { (w, h) };
+        assert!(!rect.flipped || config.allow_flipping, "{rect:?} flipped");
+        assert_eq!((rect.w, rect.h), expected, "{rect:?} size");
+        assert!(
+            rect.x >= margin
+                && rect.y >= margin
+                && rect.x + rect.w + margin <= size.w
+                && rect.y + rect.h + margin <= size.h,
+            "{rect:?} out of the {size:?} atlas"
+        );
+        for other in &live {
+            let overlaps = rect.x < other.x + other.w + gap
+                && other.x < rect.x + rect.w + gap
+                && rect.y < other.y + other.h + gap
+                && other.y < rect.y + rect.h + gap;
+            assert!(!overlaps, "{rect:?} overlaps {other:?}");
+        }
+        live.push(rect);
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let config = PackerConfig {
+        max_width: input.max_width as u32 + 1,
+        max_height: input.max_height as u32 + 1,
+        allow_flipping: input.allow_flipping,
+        padding: input.padding as u32 % 8,
+        border: input.border as u32 % 8,
+        extrude: input.extrude as u32 % 4,
+    };
+
+    let ops = &input.ops;
+    let options = input.options as usize;
+    match input.packer % 8 {
+        0 => {
+            let packer = SkylinePacker::new(config)
+                .with_heuristic(SKYLINE_HEURISTICS[options % 2])
+                .with_waste_map(options / 2 % 2 == 1);
+            run(config, packer, ops, Some(DynamicPacker::remove))
+        }
+        1 => run(
+            config,
+            SplitPacker::new(config),
+            ops,
+            Some(DynamicPacker::remove),
+        ),
+        2 => {
+            let packer = GuillotinePacker::new(config)
+                .with_choice(FREE_RECT_CHOICES[options % 6])
+                .with_split(SPLIT_RULES[options / 6 % 6])
+                .with_merge(options / 36 % 2 == 1);
+            run(config, packer, ops, Some(DynamicPacker::remove))
+        }
+        3 => {
+            let packer =
+                MaxRectsPacker::new(config).with_heuristic(MAX_RECTS_HEURISTICS[options % 5]);
+            run(config, packer, ops, Some(DynamicPacker::remove))
+        }
+        4 => {
+            let packer = BucketedShelfAllocator::new(config)
+                .with_columns(options as u32 % 4 + 1)
+                .with_buckets_per_shelf(options as u32 / 4 % 8 + 1);
+            run(config, packer, ops, Some(DynamicPacker::remove))
+        }
+        5 => run(
+            config,
+            BuddyPacker::new(config),
+            ops,
+            Some(DynamicPacker::remove),
+        ),
+        6 => run(config, StripPacker::new(config), ops, None),
+        _ => {
+            let packer = ShelfPacker::new(config).with_choice(SHELF_CHOICES[options % 6]);
+            run(config, packer, ops, None)
+        }
+    }
+});
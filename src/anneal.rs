@@ -0,0 +1,208 @@
+// local search using threshold accepting, a simulated annealing variant that accepts a worse solution
+// when it's within a threshold instead of by chance, paper: "Threshold accepting: A general purpose
+// optimization algorithm appearing superior to simulated annealing" by Gunter Dueck and Tobias Scheuer
+
+use crate::{
+    pack_order, pack_result, sort_order, PackResult, Packer, Packing, RectInput,
+    RECT_SORT_FUNCTIONS,
+};
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+/// Threshold of the first iteration, as a fraction of the area used by the starting ordering.
+const START_THRESHOLD_DIVISOR: u64 = 50;
+
+/// Configuration of [`pack_annealing`].
+#[derive(Debug, Copy, Clone)]
+pub struct AnnealConfig {
+    /// Number of orderings tried after the ones of [`RECT_SORT_FUNCTIONS`]. Default value is `1000`.
+    pub iterations: u32,
+    /// Seed of the random generator, the same seed always gives the same result. Default value is `0`.
+    pub seed: u64,
+    /// True to also search which inputs are rotated by 90 degrees, they are reported in
+    /// [`Rectf::flipped`](crate::Rectf::flipped). Only enable it when
+    /// [`PackerConfig::allow_flipping`](crate::PackerConfig::allow_flipping) is enabled too.
+    /// Default value is `false`.
+    pub rotate: bool,
+    /// Stops the search once this much time has passed, even if there are iterations left. Only checked
+    /// with the `std` feature, without a clock it's ignored. Default value is `None`.
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for AnnealConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            seed: 0,
+            rotate: false,
+            time_limit: None,
+        }
+    }
+}
+
+/// xorshift64* generator, good enough to pick the moves.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // splitmix64, so close seeds give unrelated sequences and zero is a valid seed
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Self((z ^ (z >> 31)) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Random number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (((self.next() >> 32) * n as u64) >> 32) as usize
+    }
+}
+
+/// Ordering and rotation of the inputs.
+#[derive(Clone)]
+struct State {
+    order: Vec<usize>,
+    rotated: Vec<bool>,
+}
+
+impl State {
+    /// Changes the ordering or the rotation of a random input.
+    fn mutate(&mut self, rng: &mut Rng, rotate: bool) {
+        let n = self.order.len();
+        match rng.below(if rotate { 3 } else { 2 }) {
+            0 => self.order.swap(rng.below(n), rng.below(n)),
+            1 => {
+                let input = self.order.remove(rng.below(n));
+                self.order.insert(rng.below(n), input);
+            }
+            _ => {
+                let i = rng.below(n);
+                self.rotated[i] = !self.rotated[i];
+            }
+        }
+    }
+}
+
+/// Packs the inputs with the ordering and rotation of `state`, returns the number of inputs left
+/// out and the area used, lower is better.
+fn evaluate<P: Packer + Clone, K>(
+    inputs: &[RectInput<K>],
+    state: &State,
+    sizes: &mut [RectInput<()>],
+    packer: &mut P,
+    packing: &mut Packing,
+) -> (usize, u64) {
+    for ((size, input), &rotated) in sizes.iter_mut().zip(inputs).zip(&state.rotated) {
+        size.size = input.size;
+        if rotated {
+            size.size.flip();
+        }
+    }
+
+    let area = pack_order(sizes, &state.order, packer, packing);
+    let unfit = packing.placements.iter().filter(|p| p.is_none()).count();
+    (unfit, area)
+}
+
+/// Same as [`pack`](crate::pack) but keeps searching for better orderings once the ones of
+/// [`RECT_SORT_FUNCTIONS`] are tried, for builds that can spend a lot of time to get smaller atlases.
+///
+/// Starting from the best ordering of [`RECT_SORT_FUNCTIONS`], each iteration swaps or moves an input inside
+/// the ordering (or rotates it when [`AnnealConfig::rotate`] is enabled) and keeps the change when the area
+/// used doesn't get worse by more than a threshold, which is lowered to zero as the iterations go.
+///
+/// The best packing found is returned, so it never uses more area than [`pack`](crate::pack).
+pub fn pack_annealing<P: Packer + Clone, K: Copy>(
+    inputs: &[RectInput<K>],
+    mut packer: P,
+    config: AnnealConfig,
+) -> PackResult<K> {
+    #[cfg(feature = "std")]
+    let start = std::time::Instant::now();
+
+    let n = inputs.len();
+    let mut sizes: Vec<RectInput<()>> = inputs
+        .iter()
+        .map(|input| RectInput {
+            size: input.size,
+            key: (),
+        })
+        .collect();
+
+    let mut packing = Packing::default();
+    let mut best_packing = Packing::default();
+
+    // start from the best of the sort functions
+    let mut current = State {
+        order: vec![],
+        rotated: vec![false; n],
+    };
+    let mut candidate = current.clone();
+    let mut current_score = (usize::MAX, u64::MAX);
+    for cmp in RECT_SORT_FUNCTIONS {
+        let cmp = |a: &RectInput<()>, b: &RectInput<()>| (cmp)(a.size, b.size);
+        sort_order(&sizes, &mut candidate.order, Some(&cmp));
+
+        let score = evaluate(inputs, &candidate, &mut sizes, &mut packer, &mut packing);
+        if score < current_score {
+            current_score = score;
+            current.clone_from(&candidate);
+            core::mem::swap(&mut packing, &mut best_packing);
+        }
+    }
+    let mut best_score = current_score;
+
+    let mut rng = Rng::new(config.seed);
+    let start_threshold = current_score.1 / START_THRESHOLD_DIVISOR;
+    let iterations = if n < 2 { 0 } else { config.iterations };
+
+    for i in 0..iterations {
+        // progress in thousandths, the threshold goes down linearly with it
+        let progress = i as u64 * 1000 / iterations as u64;
+        #[cfg(feature = "std")]
+        let progress = match config.time_limit {
+            Some(limit) => {
+                let elapsed = start.elapsed();
+                if elapsed >= limit {
+                    break;
+                }
+                progress.max((elapsed.as_micros() * 1000 / limit.as_micros().max(1)) as u64)
+            }
+            None => progress,
+        };
+        let threshold = start_threshold * (1000 - progress) / 1000;
+
+        candidate.clone_from(&current);
+        candidate.mutate(&mut rng, config.rotate);
+
+        let score = evaluate(inputs, &candidate, &mut sizes, &mut packer, &mut packing);
+        if score.0 > current_score.0
+            || (score.0 == current_score.0 && score.1 > current_score.1 + threshold)
+        {
+            continue;
+        }
+
+        current_score = score;
+        core::mem::swap(&mut current, &mut candidate);
+
+        if score < best_score {
+            best_score = score;
+            // rotated inputs are flipped from the point of view of the caller
+            for (placement, &rotated) in packing.placements.iter_mut().zip(&current.rotated) {
+                if let Some((rect, _)) = placement {
+                    rect.flipped ^= rotated;
+                }
+            }
+            core::mem::swap(&mut packing, &mut best_packing);
+        }
+    }
+
+    pack_result(inputs, best_packing)
+}
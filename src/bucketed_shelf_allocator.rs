@@ -0,0 +1,387 @@
+// based on: etagere https://github.com/nical/etagere
+
+use crate::{DynamicPacker, Packer, PackerConfig, Rect, Rectf, Size};
+use alloc::{vec, vec::Vec};
+
+/// Handle of an allocation made by the [`BucketedShelfAllocator`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct AllocId {
+    bucket: u32,
+    generation: u32,
+}
+
+/// Rectangle allocated by the [`BucketedShelfAllocator`].
+#[derive(Debug, Copy, Clone)]
+pub struct Allocation {
+    pub id: AllocId,
+    pub rect: Rectf,
+}
+
+#[derive(Clone)]
+struct Bucket {
+    x: u32,
+    y: u32,
+    w: u32,
+    /// Width already used by the allocations, they are placed from left to right.
+    cursor: u32,
+    /// Number of live allocations, once it gets to zero the whole bucket is available again.
+    refcount: u32,
+    generation: u32,
+    column: u32,
+}
+
+#[derive(Clone)]
+struct Shelf {
+    y: u32,
+    h: u32,
+    /// Width already given to the buckets.
+    cursor: u32,
+    /// Empty shelves don't have any bucket.
+    buckets: Vec<u32>,
+}
+
+#[derive(Clone)]
+struct Column {
+    x: u32,
+    w: u32,
+    /// Sorted by their `y` position, they cover the column from the top without any gap.
+    shelves: Vec<Shelf>,
+}
+
+impl Column {
+    /// Top of the free space at the bottom of the column.
+    fn bottom(&self) -> u32 {
+        self.shelves.last().map_or(0, |shelf| shelf.y + shelf.h)
+    }
+}
+
+/// Runtime allocator for dynamic atlases like glyph caches, the atlas is split in columns of shelves,
+/// each shelf is split in buckets and every bucket is freed once all its allocations are deallocated.
+/// Empty shelves are merged back together, so the space can be used by rectangles of another height.
+///
+/// Rectangles are never flipped.
+#[derive(Clone)]
+pub struct BucketedShelfAllocator {
+    config: PackerConfig,
+    columns_count: u32,
+    buckets_per_shelf: u32,
+    columns: Vec<Column>,
+    buckets: Vec<Bucket>,
+    free_buckets: Vec<u32>,
+    used_area: Size,
+}
+
+impl BucketedShelfAllocator {
+    pub fn new(config: PackerConfig) -> Self {
+        let mut allocator = Self {
+            config,
+            columns_count: 1,
+            buckets_per_shelf: 4,
+            columns: vec![],
+            buckets: vec![],
+            free_buckets: vec![],
+            used_area: Size::ZERO,
+        };
+        allocator.clear();
+        allocator
+    }
+
+    /// Splits the atlas in `count` columns, useful for wide atlases so shelves aren't too long.
+    /// Default value is `1`.
+    pub fn with_columns(mut self, count: u32) -> Self {
+        self.columns_count = count.max(1);
+        self.clear();
+        self
+    }
+
+    /// Number of buckets each shelf is split into, wider rectangles get a bucket of their own width.
+    /// Default value is `4`.
+    pub fn with_buckets_per_shelf(mut self, count: u32) -> Self {
+        self.buckets_per_shelf = count.max(1);
+        self
+    }
+
+    /// True when there is no live allocation.
+    pub fn is_empty(&self) -> bool {
+        self.columns.iter().all(|column| column.shelves.is_empty())
+    }
+
+    fn clear(&mut self) {
+        let bin = self.config.bin_size();
+        let w = bin.w / self.columns_count;
+
+        self.columns.clear();
+        for i in 0..self.columns_count {
+            // the last column gets the remainder
+            let x = i * w;
+            let w = if i + 1 == self.columns_count {
+                bin.w - x
+            } else {
+                w
+            };
+            self.columns.push(Column {
+                x,
+                w,
+                shelves: vec![],
+            });
+        }
+        self.buckets.clear();
+        self.free_buckets.clear();
+        self.used_area = Size::ZERO;
+    }
+
+    fn new_bucket(&mut self, bucket: Bucket) -> u32 {
+        if let Some(i) = self.free_buckets.pop() {
+            let generation = self.buckets[i as usize].generation;
+            self.buckets[i as usize] = Bucket {
+                generation,
+                ..bucket
+            };
+            i
+        } else {
+            self.buckets.push(bucket);
+            (self.buckets.len() - 1) as u32
+        }
+    }
+
+    /// Bucket of the shelf that has room for `w`, returns `None` when a new bucket is needed.
+    fn find_bucket(&self, shelf: &Shelf, w: u32) -> Option<u32> {
+        shelf
+            .buckets
+            .iter()
+            .copied()
+            .find(|&b| self.buckets[b as usize].w - self.buckets[b as usize].cursor >= w)
+    }
+
+    /// Returns the column and shelf index for a rectangle of (w, h), `None` as shelf means a new one
+    /// must be opened at the bottom of the column.
+    fn find_shelf(&self, w: u32, h: u32) -> Option<(usize, Option<usize>)> {
+        // (height waste, is empty) lower is better
+        let mut best_score = (u32::MAX, true);
+        let mut best = None;
+
+        for (c, column) in self.columns.iter().enumerate() {
+            if column.w < w {
+                continue;
+            }
+
+            for (s, shelf) in column.shelves.iter().enumerate() {
+                if shelf.h < h {
+                    continue;
+                }
+
+                let score = if shelf.buckets.is_empty() {
+                    // will be split to the exact height
+                    (0, true)
+                } else if self.find_bucket(shelf, w).is_some() || column.w - shelf.cursor >= w {
+                    (shelf.h - h, false)
+                } else {
+                    continue;
+                };
+
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some((c, Some(s)));
+                }
+            }
+        }
+
+        // a shelf much taller than the rectangle wastes too much, open a new one if possible
+        if best.is_some() && best_score.0 <= h / 2 {
+            return best;
+        }
+
+        let bin = self.config.bin_size();
+        self.columns
+            .iter()
+            .position(|column| column.w >= w && h <= bin.h - column.bottom())
+            .map(|c| (c, None))
+            .or(best)
+    }
+
+    /// Allocates a rectangle of (w, h), its space is given back with [`BucketedShelfAllocator::deallocate`].
+    /// Empty rectangles are never allocated.
+    pub fn allocate(&mut self, w: u32, h: u32) -> Option<Allocation> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
+        let (c, s) = self.find_shelf(w, h)?;
+
+        let column = &mut self.columns[c];
+        let s = match s {
+            Some(s) => s,
+            None => {
+                column.shelves.push(Shelf {
+                    y: column.bottom(),
+                    h,
+                    cursor: 0,
+                    buckets: vec![],
+                });
+                column.shelves.len() - 1
+            }
+        };
+
+        let shelf = &mut column.shelves[s];
+        if shelf.buckets.is_empty() && shelf.h > h {
+            // give the rest of the empty shelf to a new empty shelf
+            let rest = Shelf {
+                y: shelf.y + h,
+                h: shelf.h - h,
+                cursor: 0,
+                buckets: vec![],
+            };
+            shelf.h = h;
+            column.shelves.insert(s + 1, rest);
+        }
+
+        let column = &self.columns[c];
+        let shelf = &column.shelves[s];
+        let b = match self.find_bucket(shelf, w) {
+            Some(b) => b,
+            None => {
+                let bucket_w = (column.w / self.buckets_per_shelf)
+                    .max(w)
+                    .min(column.w - shelf.cursor);
+                let bucket = Bucket {
+                    x: column.x + shelf.cursor,
+                    y: shelf.y,
+                    w: bucket_w,
+                    cursor: 0,
+                    refcount: 0,
+                    generation: 0,
+                    column: c as u32,
+                };
+                let b = self.new_bucket(bucket);
+                let shelf = &mut self.columns[c].shelves[s];
+                shelf.cursor += bucket_w;
+                shelf.buckets.push(b);
+                b
+            }
+        };
+
+        let bucket = &mut self.buckets[b as usize];
+        let rect = Rectf::from_rect(Rect::new(bucket.x + bucket.cursor, bucket.y, w, h), false);
+        bucket.cursor += w;
+        bucket.refcount += 1;
+
+        self.used_area.expand_with(&rect);
+
+        Some(Allocation {
+            id: AllocId {
+                bucket: b,
+                generation: bucket.generation,
+            },
+            rect: self.config.content_rect(rect),
+        })
+    }
+
+    /// Gives back the space of an allocation, deallocating an [`AllocId`] twice is a logic error.
+    pub fn deallocate(&mut self, id: AllocId) {
+        let bucket = &mut self.buckets[id.bucket as usize];
+        debug_assert!(bucket.generation == id.generation && bucket.refcount > 0);
+        if bucket.generation != id.generation || bucket.refcount == 0 {
+            return;
+        }
+
+        bucket.refcount -= 1;
+        if bucket.refcount > 0 {
+            return;
+        }
+        bucket.cursor = 0;
+
+        let (c, y) = (bucket.column as usize, bucket.y);
+        let Ok(s) = self.columns[c]
+            .shelves
+            .binary_search_by_key(&y, |shelf| shelf.y)
+        else {
+            return;
+        };
+
+        let shelf = &self.columns[c].shelves[s];
+        if shelf
+            .buckets
+            .iter()
+            .any(|&b| self.buckets[b as usize].refcount > 0)
+        {
+            return;
+        }
+
+        // the shelf is empty, free its buckets and merge it with its empty neighbours
+        let shelf = &mut self.columns[c].shelves[s];
+        for b in shelf.buckets.drain(..) {
+            self.buckets[b as usize].generation += 1;
+            self.free_buckets.push(b);
+        }
+        shelf.cursor = 0;
+
+        self.coalesce(c, s);
+    }
+
+    fn coalesce(&mut self, c: usize, mut s: usize) {
+        let shelves = &mut self.columns[c].shelves;
+
+        if s + 1 < shelves.len() && shelves[s + 1].buckets.is_empty() {
+            shelves[s].h += shelves[s + 1].h;
+            shelves.remove(s + 1);
+        }
+
+        if s > 0 && shelves[s - 1].buckets.is_empty() {
+            shelves[s - 1].h += shelves[s].h;
+            shelves.remove(s);
+            s -= 1;
+        }
+
+        // the free space at the bottom of the column isn't a shelf
+        if s + 1 == shelves.len() {
+            shelves.pop();
+        }
+    }
+}
+
+impl DynamicPacker for BucketedShelfAllocator {
+    fn remove(&mut self, rect: &Rectf) {
+        let slot = self.config.slot_rect(rect);
+        if let Some(b) = self.buckets.iter().position(|bucket| {
+            bucket.refcount > 0
+                && bucket.y == slot.y
+                && bucket.x <= slot.x
+                && slot.x < bucket.x + bucket.cursor
+        }) {
+            self.deallocate(AllocId {
+                bucket: b as u32,
+                generation: self.buckets[b].generation,
+            });
+        }
+    }
+}
+
+impl Packer for BucketedShelfAllocator {
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        self.allocate(w, h).map(|allocation| allocation.rect)
+    }
+
+    fn reset(&mut self, resize: Option<Size>) {
+        if let Some(Size { w, h }) = resize {
+            self.config.max_width = w;
+            self.config.max_height = h;
+        }
+        self.clear();
+    }
+
+    fn grow(&mut self, size: Size) {
+        let (old, new) = self.config.grow(size);
+        if new.w > old.w {
+            // the new space on the right becomes a new column
+            self.columns.push(Column {
+                x: old.w,
+                w: new.w - old.w,
+                shelves: vec![],
+            });
+        }
+    }
+
+    fn used_area(&self) -> Size {
+        self.config.atlas_size(self.used_area)
+    }
+}
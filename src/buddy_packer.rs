@@ -0,0 +1,269 @@
+use crate::{DynamicPacker, Packer, PackerConfig, Rect, Rectf, Size};
+use alloc::{vec, vec::Vec};
+
+const NONE: u32 = u32::MAX;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Free,
+    /// Split in 4 quadrants.
+    Split,
+    /// Holds the size of the allocation.
+    Used(Size),
+    /// Released node, waiting to be reused.
+    Unused,
+}
+
+#[derive(Clone)]
+struct Node {
+    x: u32,
+    y: u32,
+    size: u32,
+    parent: u32,
+    /// First of the 4 quadrants, they are stored together as top-left, top-right, bottom-left, bottom-right.
+    children: u32,
+    state: State,
+}
+
+/// Buddy allocator, the atlas is recursively split in quadrants and each rectangle gets the smallest
+/// power of two square block that holds it. Freed blocks are merged back with their siblings.
+///
+/// Made for power of two tiles like virtual textures or lightmaps, every other size wastes the space
+/// left inside its block, see [`BuddyPacker::internal_waste`].
+#[derive(Clone)]
+pub struct BuddyPacker {
+    config: PackerConfig,
+    /// Size of each root block, the atlas is tiled with as many roots as possible.
+    root_size: u32,
+    grid: [u32; 2],
+    nodes: Vec<Node>,
+    /// Unused groups of 4 nodes.
+    free_nodes: Vec<u32>,
+    used_area: Size,
+}
+
+impl BuddyPacker {
+    pub fn new(config: PackerConfig) -> Self {
+        let mut packer = Self {
+            config,
+            root_size: 0,
+            grid: [0; 2],
+            nodes: vec![],
+            free_nodes: vec![],
+            used_area: Size::ZERO,
+        };
+        packer.clear();
+        packer
+    }
+
+    fn clear(&mut self) {
+        let bin = self.config.bin_size();
+        let min_side = bin.min_side();
+
+        // biggest power of two that fits the atlas
+        self.root_size = if min_side == 0 {
+            0
+        } else {
+            1 << (31 - min_side.leading_zeros())
+        };
+        self.grid = [0; 2];
+        self.nodes.clear();
+        self.free_nodes.clear();
+        self.used_area = Size::ZERO;
+
+        self.add_roots();
+    }
+
+    /// Fills the atlas with root blocks, keeping the existing ones.
+    fn add_roots(&mut self) {
+        if self.root_size == 0 {
+            return;
+        }
+
+        let bin = self.config.bin_size();
+        let grid = [bin.w / self.root_size, bin.h / self.root_size];
+        for y in 0..grid[1] {
+            for x in 0..grid[0] {
+                if x < self.grid[0] && y < self.grid[1] {
+                    continue;
+                }
+                self.nodes.push(Node {
+                    x: x * self.root_size,
+                    y: y * self.root_size,
+                    size: self.root_size,
+                    parent: NONE,
+                    children: NONE,
+                    state: State::Free,
+                });
+            }
+        }
+        self.grid = [self.grid[0].max(grid[0]), self.grid[1].max(grid[1])];
+    }
+
+    /// Smallest free block that can hold a square of `size`, the ones near the top-left win ties.
+    fn find_block(&self, size: u32) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.state != State::Free || node.size < size {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some(b) => {
+                    let b = &self.nodes[b];
+                    (node.size, node.y, node.x) < (b.size, b.y, b.x)
+                }
+            };
+            if better {
+                best = Some(i);
+            }
+        }
+        best
+    }
+
+    /// Splits the node `n` in 4 quadrants, returns the first of them.
+    fn split(&mut self, n: usize) -> usize {
+        let Node { x, y, size, .. } = self.nodes[n];
+        let half = size / 2;
+        let quadrants = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)];
+
+        let first = match self.free_nodes.pop() {
+            Some(first) => first as usize,
+            None => {
+                let first = self.nodes.len();
+                self.nodes.resize(
+                    first + 4,
+                    Node {
+                        x: 0,
+                        y: 0,
+                        size: 0,
+                        parent: NONE,
+                        children: NONE,
+                        state: State::Unused,
+                    },
+                );
+                first
+            }
+        };
+
+        for (i, (x, y)) in quadrants.into_iter().enumerate() {
+            self.nodes[first + i] = Node {
+                x,
+                y,
+                size: half,
+                parent: n as u32,
+                children: NONE,
+                state: State::Free,
+            };
+        }
+
+        self.nodes[n].children = first as u32;
+        self.nodes[n].state = State::Split;
+        first
+    }
+
+    /// Used block that starts at (x, y).
+    fn find_used(&self, x: u32, y: u32) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| matches!(node.state, State::Used(_)) && node.x == x && node.y == y)
+    }
+
+    /// Space left unused inside the block of an allocation, padding and extrusion included,
+    /// returns `None` if `rect` isn't allocated.
+    pub fn internal_waste(&self, rect: &Rectf) -> Option<u64> {
+        let slot = self.config.slot_rect(rect);
+        let node = &self.nodes[self.find_used(slot.x, slot.y)?];
+        match node.state {
+            State::Used(size) => Some(node.size as u64 * node.size as u64 - size.area()),
+            _ => None,
+        }
+    }
+
+    /// Sum of the [`BuddyPacker::internal_waste`] of every allocation.
+    pub fn total_waste(&self) -> u64 {
+        self.nodes
+            .iter()
+            .map(|node| match node.state {
+                State::Used(size) => node.size as u64 * node.size as u64 - size.area(),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+impl DynamicPacker for BuddyPacker {
+    fn remove(&mut self, rect: &Rectf) {
+        let slot = self.config.slot_rect(rect);
+        let Some(mut n) = self.find_used(slot.x, slot.y) else {
+            return;
+        };
+        self.nodes[n].state = State::Free;
+
+        // merge the siblings back into their parent while all of them are free
+        while self.nodes[n].parent != NONE {
+            let parent = self.nodes[n].parent as usize;
+            let first = self.nodes[parent].children as usize;
+            if self.nodes[first..first + 4]
+                .iter()
+                .any(|node| node.state != State::Free)
+            {
+                break;
+            }
+
+            for node in &mut self.nodes[first..first + 4] {
+                node.state = State::Unused;
+            }
+            self.free_nodes.push(first as u32);
+
+            self.nodes[parent].children = NONE;
+            self.nodes[parent].state = State::Free;
+            n = parent;
+        }
+    }
+}
+
+impl Packer for BuddyPacker {
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
+        let size = w.max(h).max(1).checked_next_power_of_two()?;
+
+        let mut n = self.find_block(size)?;
+        while self.nodes[n].size > size {
+            n = self.split(n);
+        }
+
+        let node = &mut self.nodes[n];
+        node.state = State::Used(Size::new(w, h));
+
+        let rect = Rectf::from_rect(Rect::new(node.x, node.y, w, h), false);
+        self.used_area.expand_with(&rect);
+
+        Some(self.config.content_rect(rect))
+    }
+
+    fn reset(&mut self, resize: Option<Size>) {
+        if let Some(Size { w, h }) = resize {
+            self.config.max_width = w;
+            self.config.max_height = h;
+        }
+        self.clear();
+    }
+
+    fn grow(&mut self, size: Size) {
+        self.config.grow(size);
+        if self.root_size == 0 {
+            self.clear();
+        } else {
+            // the root size is kept, so the existing blocks don't move
+            self.add_roots();
+        }
+    }
+
+    fn used_area(&self) -> Size {
+        self.config.atlas_size(self.used_area)
+    }
+}
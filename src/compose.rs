@@ -0,0 +1,161 @@
+use crate::{PackerConfig, Rect, RectOutput, Size, Trim};
+use alloc::vec::Vec;
+use image::{GenericImageView, Rgba, RgbaImage};
+
+/// Configuration of [`compose`].
+#[derive(Debug, Copy, Clone)]
+pub struct ComposeConfig {
+    /// Color of the pixels not covered by any image, including the [`PackerConfig::padding`] and
+    /// [`PackerConfig::border`]. Default value is transparent black.
+    pub background: Rgba<u8>,
+}
+
+impl Default for ComposeConfig {
+    fn default() -> Self {
+        Self {
+            background: Rgba([0, 0, 0, 0]),
+        }
+    }
+}
+
+/// Problem found by [`compose`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ComposeError<K> {
+    /// No source image was given for the key.
+    MissingImage { key: K },
+    /// Source image with a size that doesn't match its rectangle, once flipped back. For
+    /// [`compose_trimmed`] it's the size of the [`Trim::rect`], or of the whole source when the trim
+    /// doesn't belong to it.
+    SizeMismatch { key: K, expected: Size, found: Size },
+    /// Rectangle inside an atlas that doesn't exist.
+    UnknownAtlas { key: K, atlas: usize },
+}
+
+/// Builds one RGBA image per atlas, of the sizes in `atlas_sizes`, by copying the source image of each
+/// output to its rectangle; `image` gives the source of a key, it must have the size of the input.
+///
+/// Sources of rectangles with [`Rectf::flipped`](crate::Rectf::flipped) are rotated 90 degrees clockwise
+/// and the edges of every image are duplicated over the [`PackerConfig::extrude`] pixels around it, so
+/// `config` must be the one used to pack the `outputs`.
+pub fn compose<'a, K, I, F>(
+    outputs: &[RectOutput<K>],
+    atlas_sizes: &[Size],
+    config: &PackerConfig,
+    compose_config: ComposeConfig,
+    mut image: F,
+) -> Result<Vec<RgbaImage>, ComposeError<K>>
+where
+    K: Copy,
+    I: GenericImageView<Pixel = Rgba<u8>> + 'a,
+    F: FnMut(K) -> Option<&'a I>,
+{
+    compose_regions(outputs, atlas_sizes, config, compose_config, |key| {
+        let source = image(key)?;
+        let (w, h) = source.dimensions();
+        Some((source, Rect::new(0, 0, w, h)))
+    })
+}
+
+/// Same as [`compose`] for inputs made by [`trim`](crate::trim), only the [`Trim::rect`] of each source
+/// image is copied; `image` gives the untrimmed source of a key and its trim.
+pub fn compose_trimmed<'a, K, I, F>(
+    outputs: &[RectOutput<K>],
+    atlas_sizes: &[Size],
+    config: &PackerConfig,
+    compose_config: ComposeConfig,
+    mut image: F,
+) -> Result<Vec<RgbaImage>, ComposeError<K>>
+where
+    K: Copy,
+    I: GenericImageView<Pixel = Rgba<u8>> + 'a,
+    F: FnMut(K) -> Option<(&'a I, Trim)>,
+{
+    compose_regions(outputs, atlas_sizes, config, compose_config, |key| {
+        let (source, trim) = image(key)?;
+        let (w, h) = source.dimensions();
+        // a trim of a different image is reported as a mismatch instead of reading out of bounds
+        let inside = trim.source_size == Size::new(w, h)
+            && trim.rect.x as u64 + trim.rect.w as u64 <= w as u64
+            && trim.rect.y as u64 + trim.rect.h as u64 <= h as u64;
+        let region = if inside {
+            trim.rect
+        } else {
+            Rect::new(0, 0, w, h)
+        };
+        Some((source, region))
+    })
+}
+
+/// Copies the `region` of each source image, given by `image`.
+fn compose_regions<'a, K, I, F>(
+    outputs: &[RectOutput<K>],
+    atlas_sizes: &[Size],
+    config: &PackerConfig,
+    compose_config: ComposeConfig,
+    mut image: F,
+) -> Result<Vec<RgbaImage>, ComposeError<K>>
+where
+    K: Copy,
+    I: GenericImageView<Pixel = Rgba<u8>> + 'a,
+    F: FnMut(K) -> Option<(&'a I, Rect)>,
+{
+    let mut atlases: Vec<RgbaImage> = atlas_sizes
+        .iter()
+        .map(|size| RgbaImage::from_pixel(size.w, size.h, compose_config.background))
+        .collect();
+
+    for output in outputs {
+        let key = output.key;
+        let rect = &output.rect;
+
+        let Some(atlas) = atlases.get_mut(output.atlas) else {
+            return Err(ComposeError::UnknownAtlas {
+                key,
+                atlas: output.atlas,
+            });
+        };
+        let Some((source, region)) = image(key) else {
+            return Err(ComposeError::MissingImage { key });
+        };
+
+        let (w, h) = (region.w, region.h);
+        let mut expected = rect.size();
+        if rect.flipped {
+            expected.flip();
+        }
+        if (w, h) != (expected.w, expected.h) {
+            return Err(ComposeError::SizeMismatch {
+                key,
+                expected,
+                found: Size::new(w, h),
+            });
+        }
+        if rect.w == 0 || rect.h == 0 {
+            continue;
+        }
+
+        // the extruded pixels repeat the closest pixel inside the rectangle
+        let e = config.extrude as i64;
+        for dy in -e..rect.h as i64 + e {
+            for dx in -e..rect.w as i64 + e {
+                let (x, y) = (rect.x as i64 + dx, rect.y as i64 + dy);
+                if x < 0 || y < 0 || x >= atlas.width() as i64 || y >= atlas.height() as i64 {
+                    continue;
+                }
+
+                let cx = dx.clamp(0, rect.w as i64 - 1) as u32;
+                let cy = dy.clamp(0, rect.h as i64 - 1) as u32;
+                // a clockwise rotation moves the left column of the source to the top row
+                let (sx, sy) = if rect.flipped {
+                    (cy, h - 1 - cx)
+                } else {
+                    (cx, cy)
+                };
+                let pixel = source.get_pixel(region.x + sx, region.y + sy);
+                atlas.put_pixel(x as u32, y as u32, pixel);
+            }
+        }
+    }
+
+    Ok(atlases)
+}
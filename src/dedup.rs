@@ -0,0 +1,137 @@
+use crate::validate::find_inputs;
+use crate::{PackResult, RectInput, RectOutput, Rectf};
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+#[cfg(feature = "image")]
+use image::{GenericImageView, Rgba};
+
+/// Inputs with the same content grouped together, made by [`dedup`].
+#[derive(Clone)]
+pub struct Dedup<K> {
+    /// First input of each content, the ones to pack.
+    pub unique: Vec<RectInput<K>>,
+    /// Key of every input, in the order given to [`dedup`].
+    keys: Vec<K>,
+    /// Index inside `unique` of every input.
+    indices: Vec<usize>,
+}
+
+/// Groups the inputs with the same size and `hash`, so identical images (like the hold frames of an
+/// animation) share a single rectangle.
+///
+/// Pack [`Dedup::unique`] with any of the packing functions, then give the result to [`Dedup::expand`]
+/// to get a rectangle for every input. The `hash` is trusted, inputs with the same one are always
+/// considered identical; when it can collide, like `content_hash` (with the `image` feature), use
+/// [`dedup_by`] to compare their contents.
+pub fn dedup<K: Copy, H: Ord>(
+    inputs: &[RectInput<K>],
+    hash: impl FnMut(&RectInput<K>) -> H,
+) -> Dedup<K> {
+    dedup_by(inputs, hash, |_, _| true)
+}
+
+/// Same as [`dedup`] but inputs with the same size and `hash` are only grouped when `same` is true for
+/// them, so a collision of the `hash` can't give an input the rectangle of a different one.
+///
+/// `same` is called with the [`Dedup::unique`] input first, for images use `same_content` (with the
+/// `image` feature) or a comparison of your own.
+pub fn dedup_by<K: Copy, H: Ord>(
+    inputs: &[RectInput<K>],
+    mut hash: impl FnMut(&RectInput<K>) -> H,
+    mut same: impl FnMut(&RectInput<K>, &RectInput<K>) -> bool,
+) -> Dedup<K> {
+    let mut unique: Vec<RectInput<K>> = vec![];
+    // indices inside `unique` of the inputs with each size and hash
+    let mut found: BTreeMap<_, Vec<usize>> = BTreeMap::new();
+    let indices = inputs
+        .iter()
+        .map(|input| {
+            let candidates = found
+                .entry((input.size.w, input.size.h, hash(input)))
+                .or_default();
+            match candidates.iter().find(|&&i| same(&unique[i], input)) {
+                Some(&i) => i,
+                None => {
+                    unique.push(*input);
+                    candidates.push(unique.len() - 1);
+                    unique.len() - 1
+                }
+            }
+        })
+        .collect();
+
+    Dedup {
+        unique,
+        keys: inputs.iter().map(|input| input.key).collect(),
+        indices,
+    }
+}
+
+impl<K: Copy + PartialEq> Dedup<K> {
+    /// Number of inputs that share the content of an input before them.
+    pub fn alias_count(&self) -> usize {
+        self.keys.len() - self.unique.len()
+    }
+
+    /// Pairs of (alias, key), for each input that shares the content of the [`Dedup::unique`] input `key`.
+    pub fn aliases(&self) -> impl Iterator<Item = (K, K)> + '_ {
+        self.keys
+            .iter()
+            .zip(&self.indices)
+            .map(|(&key, &i)| (key, self.unique[i].key))
+            .filter(|(alias, key)| alias != key)
+    }
+
+    /// Gives every input the [`Rectf`] of the [`Dedup::unique`] input with the same content, `result`
+    /// must come from packing [`Dedup::unique`].
+    ///
+    /// The aliases are reported in [`PackResult::unfit`] or [`PackResult::overflow`] along with their
+    /// unique input. Since aliases overlap, [`validate`](crate::validate) the result before expanding it.
+    pub fn expand(&self, result: &PackResult<K>) -> PackResult<K> {
+        let mut placements: Vec<Option<(Rectf, usize)>> = vec![None; self.unique.len()];
+        for (output, found) in result
+            .rects
+            .iter()
+            .zip(find_inputs(&self.unique, &result.rects))
+        {
+            if let Some(i) = found {
+                placements[i] = Some((output.rect, output.atlas));
+            }
+        }
+
+        let mut expanded = PackResult {
+            atlas_sizes: result.atlas_sizes.clone(),
+            ..PackResult::default()
+        };
+        for (&key, &i) in self.keys.iter().zip(&self.indices) {
+            match placements[i] {
+                Some((rect, atlas)) => expanded.rects.push(RectOutput { rect, atlas, key }),
+                None if result.overflow.contains(&self.unique[i].key) => {
+                    expanded.overflow.push(key)
+                }
+                None => expanded.unfit.push(key),
+            }
+        }
+        expanded
+    }
+}
+
+/// FNV-1a hash of the size and pixels of an image, to be used with [`dedup_by`] and [`same_content`].
+#[cfg(feature = "image")]
+pub fn content_hash<I: GenericImageView<Pixel = Rgba<u8>>>(image: &I) -> u64 {
+    let (w, h) = image.dimensions();
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let bytes = w.to_le_bytes().into_iter().chain(h.to_le_bytes());
+    let pixels = image.pixels().flat_map(|(_, _, pixel)| pixel.0);
+    for byte in bytes.chain(pixels) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100_0000_01b3);
+    }
+    hash
+}
+
+/// Whether two images have the same size and pixels, to check the inputs grouped by [`content_hash`] in
+/// [`dedup_by`].
+#[cfg(feature = "image")]
+pub fn same_content<I: GenericImageView<Pixel = Rgba<u8>>>(a: &I, b: &I) -> bool {
+    a.dimensions() == b.dimensions() && a.pixels().eq(b.pixels())
+}
@@ -0,0 +1,376 @@
+// libGDX TextureAtlas text format, a page is its image name followed by its fields, then each region
+// is its name followed by its fields; pages are separated by a blank line. Fields are told apart from
+// names by their `:`, the indentation is optional. libGDX turns rotated regions 90 degrees
+// counter-clockwise (`rotate:true`), the opposite of the packers of this crate. The offsets of a trimmed
+// region are measured from the bottom-left of the original image, unlike the rest of the file
+
+use crate::{Rect, RectInput, RectOutput, Rectf, Size, Trim};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+/// Configuration of [`to_gdx_atlas`].
+#[derive(Debug, Clone)]
+pub struct GdxConfig {
+    /// File name of the image of each page, `{n}` is replaced by the index of the atlas.
+    /// Default value is `"atlas{n}.png"`.
+    pub image: String,
+    /// Pixel format of the pages. Default value is `"RGBA8888"`.
+    pub format: String,
+    /// Minification and magnification filters of the pages. Default value is `["Nearest", "Nearest"]`.
+    pub filter: [String; 2],
+    /// Texture wrap of the pages, `none`, `x`, `y` or `xy`. Default value is `"none"`.
+    pub repeat: String,
+}
+
+impl Default for GdxConfig {
+    fn default() -> Self {
+        Self {
+            image: "atlas{n}.png".to_string(),
+            format: "RGBA8888".to_string(),
+            filter: ["Nearest".to_string(), "Nearest".to_string()],
+            repeat: "none".to_string(),
+        }
+    }
+}
+
+/// Page of a [`GdxAtlas`], one for each atlas image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdxPage {
+    pub image: String,
+    pub size: Size,
+    pub format: String,
+    pub filter: [String; 2],
+    pub repeat: String,
+}
+
+/// Region of a [`GdxAtlas`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdxRegion {
+    pub name: String,
+    /// Frame number of the animation named `name`, `-1` when it isn't a frame.
+    pub index: i32,
+    /// Index of the page, like [`RectOutput::atlas`].
+    pub page: usize,
+    /// Placement inside the page, it's `flipped` when the region is rotated either way.
+    pub rect: Rectf,
+    /// Counter-clockwise rotation of the region inside the page, `0`, `90` (libGDX `rotate:true`) or
+    /// `270` (the clockwise rotation of [`Rectf::flipped`]).
+    pub degrees: u32,
+    /// Part of the original image that was packed.
+    pub trim: Trim,
+}
+
+impl GdxRegion {
+    /// Full name of the region with its frame number, the key given to [`to_gdx_atlas`].
+    pub fn key(&self) -> String {
+        if self.index < 0 {
+            self.name.clone()
+        } else {
+            format!("{}_{}", self.name, self.index)
+        }
+    }
+}
+
+/// Contents of a libGDX `.atlas` file, read by [`parse_gdx_atlas`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GdxAtlas {
+    pub pages: Vec<GdxPage>,
+    pub regions: Vec<GdxRegion>,
+}
+
+impl GdxAtlas {
+    /// Size of each page, like [`PackResult::atlas_sizes`](crate::PackResult::atlas_sizes).
+    pub fn atlas_sizes(&self) -> Vec<Size> {
+        self.pages.iter().map(|page| page.size).collect()
+    }
+
+    /// Input of each region keyed by its index in [`GdxAtlas::regions`], to [`validate`](crate::validate)
+    /// the placements given by [`GdxAtlas::outputs`].
+    pub fn inputs(&self) -> Vec<RectInput<usize>> {
+        self.regions
+            .iter()
+            .enumerate()
+            .map(|(key, region)| RectInput {
+                size: region.trim.rect.size(),
+                key,
+            })
+            .collect()
+    }
+
+    /// Placement of each region keyed by its index in [`GdxAtlas::regions`].
+    pub fn outputs(&self) -> Vec<RectOutput<usize>> {
+        self.regions
+            .iter()
+            .enumerate()
+            .map(|(key, region)| RectOutput {
+                rect: region.rect,
+                atlas: region.page,
+                key,
+            })
+            .collect()
+    }
+}
+
+/// Problem found by [`parse_gdx_atlas`] or [`to_gdx_atlas`], the lines start at 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GdxError {
+    /// Field where the image of a new page should be.
+    Syntax { line: usize },
+    /// Field with a value that can't be parsed.
+    InvalidValue { line: usize },
+    /// Region rotated by other than 0, 90 or 270 degrees.
+    UnsupportedRotation { line: usize },
+    /// Region without its `bounds`, or its `xy` and `size` in the legacy format.
+    MissingBounds { line: usize },
+    /// Key that can't be written as a region name: empty, with a `:` or a line break, or with spaces
+    /// around it. It's the index inside the `outputs`.
+    InvalidName { output: usize },
+    /// Flipped output, libGDX would load it turned the other way, pack with
+    /// [`PackerConfig::allow_flipping`](crate::PackerConfig::allow_flipping) disabled. It's the index
+    /// inside the `outputs`.
+    FlippedOutput { output: usize },
+}
+
+/// Splits the frame number off names like `run_3`, like the libGDX packer does.
+fn split_index(name: &str) -> (&str, i32) {
+    if let Some((base, index)) = name.rsplit_once('_') {
+        if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(index) = index.parse() {
+                return (base, index);
+            }
+        }
+    }
+    (name, -1)
+}
+
+/// Writes a libGDX `.atlas` file with a page for each atlas of `atlas_sizes` and a region for each of
+/// the `outputs` inside it, named by its key. Keys ending with an underscore and a number, like `run_3`,
+/// are written as the frame `3` of the region `run`.
+///
+/// `trim` gives the [`Trim`] of the images that were trimmed before packing, for the `offsets` of their
+/// region; return `None` for the ones packed whole.
+///
+/// Fails with [`GdxError::InvalidName`] when a key can't be read back as a region name, and with
+/// [`GdxError::FlippedOutput`] for flipped outputs since libGDX only rotates counter-clockwise.
+pub fn to_gdx_atlas<K: AsRef<str>>(
+    outputs: &[RectOutput<K>],
+    atlas_sizes: &[Size],
+    config: &GdxConfig,
+    mut trim: impl FnMut(&K) -> Option<Trim>,
+) -> Result<String, GdxError> {
+    let mut atlas = String::new();
+    for (page, size) in atlas_sizes.iter().enumerate() {
+        if page > 0 {
+            atlas.push('\n');
+        }
+        let image = config.image.replace("{n}", &page.to_string());
+        // writing to a String can't fail
+        let _ = writeln!(atlas, "{image}");
+        let _ = writeln!(atlas, "size:{},{}", size.w, size.h);
+        let _ = writeln!(atlas, "format:{}", config.format);
+        let _ = writeln!(atlas, "filter:{},{}", config.filter[0], config.filter[1]);
+        let _ = writeln!(atlas, "repeat:{}", config.repeat);
+
+        for (i, output) in outputs.iter().enumerate() {
+            if output.atlas != page {
+                continue;
+            }
+            let rect = &output.rect;
+            if rect.flipped {
+                return Err(GdxError::FlippedOutput { output: i });
+            }
+            let packed = rect.size();
+            let trim = trim(&output.key).unwrap_or(Trim::none(packed));
+            let bottom = trim.source_size.h as i64 - trim.rect.y as i64 - packed.h as i64;
+            let (name, index) = split_index(output.key.as_ref());
+            if name.is_empty() || name.trim() != name || name.contains([':', '\n', '\r']) {
+                return Err(GdxError::InvalidName { output: i });
+            }
+
+            let _ = writeln!(atlas, "{name}");
+            let _ = writeln!(
+                atlas,
+                "  bounds:{},{},{},{}",
+                rect.x, rect.y, packed.w, packed.h
+            );
+            let _ = writeln!(
+                atlas,
+                "  offsets:{},{},{},{}",
+                trim.rect.x, bottom, trim.source_size.w, trim.source_size.h
+            );
+            let _ = writeln!(atlas, "  index:{index}");
+        }
+    }
+    Ok(atlas)
+}
+
+/// Region being read, the fields can come in any order.
+#[derive(Default)]
+struct Fields {
+    line: usize,
+    name: String,
+    bounds: Option<[u32; 4]>,
+    xy: Option<[u32; 2]>,
+    size: Option<[u32; 2]>,
+    offsets: Option<[i64; 4]>,
+    offset: Option<[i64; 2]>,
+    orig: Option<[u32; 2]>,
+    degrees: u32,
+    index: i32,
+}
+
+impl Fields {
+    fn region(self, page: usize) -> Result<GdxRegion, GdxError> {
+        let [x, y, w, h] = match (self.bounds, self.xy, self.size) {
+            (Some(bounds), _, _) => bounds,
+            (None, Some([x, y]), Some([w, h])) => [x, y, w, h],
+            _ => return Err(GdxError::MissingBounds { line: self.line }),
+        };
+        let offsets = self.offsets.or(match (self.offset, self.orig) {
+            (Some([ox, oy]), Some([ow, oh])) => Some([ox, oy, ow as i64, oh as i64]),
+            _ => None,
+        });
+
+        let trim = match offsets {
+            Some([ox, oy, ow, oh]) => {
+                // back to the top-left of the original image
+                let top = oh - oy - h as i64;
+                let valid = |v: i64| u32::try_from(v).ok();
+                match (valid(ox), valid(top), valid(ow), valid(oh)) {
+                    (Some(ox), Some(top), Some(ow), Some(oh)) => Trim {
+                        rect: Rect::new(ox, top, w, h),
+                        source_size: Size::new(ow, oh),
+                    },
+                    _ => return Err(GdxError::InvalidValue { line: self.line }),
+                }
+            }
+            None => Trim::none(Size::new(w, h)),
+        };
+
+        let flipped = self.degrees != 0;
+        let (w, h) = if flipped { (h, w) } else { (w, h) };
+        Ok(GdxRegion {
+            name: self.name,
+            index: self.index,
+            page,
+            rect: Rectf {
+                x,
+                y,
+                w,
+                h,
+                flipped,
+            },
+            degrees: self.degrees,
+            trim,
+        })
+    }
+}
+
+/// Parses `N` comma separated values.
+fn values<T: core::str::FromStr, const N: usize>(
+    value: &str,
+    line: usize,
+) -> Result<[T; N], GdxError> {
+    let mut parts = value.split(',').map(|part| part.trim().parse::<T>());
+    let values = core::array::from_fn(|_| parts.next().and_then(Result::ok));
+    if parts.next().is_some() || values.iter().any(Option::is_none) {
+        return Err(GdxError::InvalidValue { line });
+    }
+    Ok(values.map(Option::unwrap))
+}
+
+/// Reads a libGDX `.atlas` file, both the current format and the legacy one (with `xy`, `size`, `orig`
+/// and `offset` fields) are supported, with or without indented fields. Unknown fields are skipped.
+pub fn parse_gdx_atlas(atlas: &str) -> Result<GdxAtlas, GdxError> {
+    let mut parsed = GdxAtlas::default();
+    let mut region: Option<Fields> = None;
+    // true after a blank line, the next line is the image of a new page
+    let mut new_page = true;
+
+    for (n, text) in atlas.lines().enumerate() {
+        let line = n + 1;
+        let text = text.trim();
+        if text.is_empty() {
+            if let Some(fields) = region.take() {
+                parsed.regions.push(fields.region(parsed.pages.len() - 1)?);
+            }
+            new_page = true;
+            continue;
+        }
+
+        let Some((key, value)) = text.split_once(':') else {
+            if new_page {
+                parsed.pages.push(GdxPage {
+                    image: text.to_string(),
+                    size: Size::ZERO,
+                    format: "RGBA8888".to_string(),
+                    filter: ["Nearest".to_string(), "Nearest".to_string()],
+                    repeat: "none".to_string(),
+                });
+                new_page = false;
+                continue;
+            }
+            // a region name
+            if let Some(fields) = region.take() {
+                parsed.regions.push(fields.region(parsed.pages.len() - 1)?);
+            }
+            region = Some(Fields {
+                line,
+                name: text.to_string(),
+                index: -1,
+                ..Fields::default()
+            });
+            continue;
+        };
+        if new_page {
+            return Err(GdxError::Syntax { line });
+        }
+        let (key, value) = (key.trim(), value.trim());
+
+        match &mut region {
+            None => {
+                let page = parsed.pages.last_mut().unwrap();
+                match key {
+                    "size" => {
+                        let [w, h] = values(value, line)?;
+                        page.size = Size::new(w, h);
+                    }
+                    "format" => page.format = value.to_string(),
+                    "filter" => {
+                        let [min, mag] = values::<String, 2>(value, line)?;
+                        page.filter = [min, mag];
+                    }
+                    "repeat" => page.repeat = value.to_string(),
+                    _ => {}
+                }
+            }
+            Some(fields) => match key {
+                "bounds" => fields.bounds = Some(values(value, line)?),
+                "xy" => fields.xy = Some(values(value, line)?),
+                "size" => fields.size = Some(values(value, line)?),
+                "offsets" => fields.offsets = Some(values(value, line)?),
+                "offset" => fields.offset = Some(values(value, line)?),
+                "orig" => fields.orig = Some(values(value, line)?),
+                "index" => fields.index = values::<i32, 1>(value, line)?[0],
+                "rotate" => {
+                    fields.degrees = match value {
+                        "true" | "90" => 90,
+                        "false" | "0" => 0,
+                        "270" => 270,
+                        _ => return Err(GdxError::UnsupportedRotation { line }),
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    if let Some(fields) = region.take() {
+        parsed.regions.push(fields.region(parsed.pages.len() - 1)?);
+    }
+    Ok(parsed)
+}
@@ -0,0 +1,263 @@
+// based on: RectangleBinPack https://github.com/juj/RectangleBinPack
+// paper: "A Thousand Ways to Pack the Bin" by Jukka Jylänki
+
+use crate::{merge_rects, DynamicPacker, Packer, PackerConfig, Rect, Rectf, Size};
+use alloc::{vec, vec::Vec};
+
+/// Rule used by [`GuillotinePacker`] to choose the free space that receives each rectangle.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum FreeRectChoice {
+    /// Smallest free space the rectangle fits.
+    #[default]
+    BestAreaFit,
+    /// Free space that leaves the smallest leftover on the shorter side.
+    BestShortSideFit,
+    /// Free space that leaves the smallest leftover on the longer side.
+    BestLongSideFit,
+    /// Biggest free space the rectangle fits.
+    WorstAreaFit,
+    /// Free space that leaves the biggest leftover on the shorter side.
+    WorstShortSideFit,
+    /// Free space that leaves the biggest leftover on the longer side.
+    WorstLongSideFit,
+}
+
+/// Rule used by [`GuillotinePacker`] to split the leftover of a free space after placing a rectangle.
+///
+/// A horizontal split gives the whole width of the free space to the leftover below the rectangle,
+/// a vertical split gives the whole height to the leftover on its right.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SplitRule {
+    /// Split along the axis with less leftover space, same rule used by the [`SplitPacker`](crate::SplitPacker).
+    #[default]
+    ShorterLeftoverAxis,
+    /// Split along the axis with more leftover space.
+    LongerLeftoverAxis,
+    /// Make the smaller of the two leftovers as small as possible.
+    MinimizeArea,
+    /// Make the bigger of the two leftovers as big as possible.
+    MaximizeArea,
+    /// Split along the shorter side of the free space.
+    ShorterAxis,
+    /// Split along the longer side of the free space.
+    LongerAxis,
+}
+
+/// Keeps a list of disjoint free spaces, each placement splits the chosen space in two.
+///
+/// A configurable version of the [`SplitPacker`](crate::SplitPacker), use it to tune the packing to a
+/// specific data set.
+#[derive(Clone)]
+pub struct GuillotinePacker {
+    config: PackerConfig,
+    choice: FreeRectChoice,
+    split: SplitRule,
+    merge: bool,
+    free: Vec<Rect>,
+    used_area: Size,
+}
+
+impl GuillotinePacker {
+    pub fn new(config: PackerConfig) -> Self {
+        let bin = config.bin_size();
+        Self {
+            config,
+            choice: FreeRectChoice::default(),
+            split: SplitRule::default(),
+            merge: false,
+            free: vec![Rect::new(0, 0, bin.w, bin.h)],
+            used_area: Size::ZERO,
+        }
+    }
+
+    /// Changes the rule used to choose the free space of each rectangle.
+    pub fn with_choice(mut self, choice: FreeRectChoice) -> Self {
+        self.choice = choice;
+        self
+    }
+
+    /// Changes the rule used to split the free spaces.
+    pub fn with_split(mut self, split: SplitRule) -> Self {
+        self.split = split;
+        self
+    }
+
+    /// When enabled adjacent free spaces that share a whole edge are merged after each insertion.
+    /// Default value is `false`.
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Same as [`GuillotinePacker::new`] but without any free space, used by packers that only
+    /// want to track the spaces they give with [`GuillotinePacker::add_free`].
+    pub(crate) fn empty(config: PackerConfig) -> Self {
+        let mut packer = Self::new(config);
+        packer.free.clear();
+        packer
+    }
+
+    /// Gives a free space to the packer, it must not overlap any other free space or placed rectangle.
+    pub(crate) fn add_free(&mut self, rect: Rect) {
+        if rect.area() == 0 {
+            return;
+        }
+        self.free.push(rect);
+        if self.merge {
+            merge_rects(&mut self.free);
+        }
+    }
+
+    /// Removes every free space, keeping the configuration.
+    pub(crate) fn clear(&mut self) {
+        self.free.clear();
+        self.used_area = Size::ZERO;
+    }
+
+    /// How well a rectangle of (w, h) fits inside the `free` space, lower scores are better.
+    fn score(&self, free: &Rect, w: u32, h: u32) -> (i64, i64) {
+        let leftover_w = (free.w - w) as i64;
+        let leftover_h = (free.h - h) as i64;
+        let short_side = leftover_w.min(leftover_h);
+        let long_side = leftover_w.max(leftover_h);
+        let area = (free.area() - w as u64 * h as u64) as i64;
+
+        match self.choice {
+            FreeRectChoice::BestAreaFit => (area, short_side),
+            FreeRectChoice::BestShortSideFit => (short_side, long_side),
+            FreeRectChoice::BestLongSideFit => (long_side, short_side),
+            FreeRectChoice::WorstAreaFit => (-area, -short_side),
+            FreeRectChoice::WorstShortSideFit => (-short_side, -long_side),
+            FreeRectChoice::WorstLongSideFit => (-long_side, -short_side),
+        }
+    }
+
+    /// Finds the free space for a rectangle of (w, h), returns its index and the placed rectangle.
+    fn find_position(&self, w: u32, h: u32) -> Option<(usize, Rectf)> {
+        let mut best_score = (i64::MAX, i64::MAX);
+        let mut best = None;
+
+        for (i, free) in self.free.iter().enumerate() {
+            // a perfect fit can't be beaten
+            if free.w == w && free.h == h {
+                return Some((i, Rectf::from_rect(Rect::new(free.x, free.y, w, h), false)));
+            }
+            if self.config.allow_flipping && free.w == h && free.h == w {
+                return Some((i, Rectf::from_rect(Rect::new(free.x, free.y, h, w), true)));
+            }
+
+            if free.w >= w && free.h >= h {
+                let score = self.score(free, w, h);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some((i, Rectf::from_rect(Rect::new(free.x, free.y, w, h), false)));
+                }
+            }
+
+            if self.config.allow_flipping && w != h && free.w >= h && free.h >= w {
+                let score = self.score(free, h, w);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some((i, Rectf::from_rect(Rect::new(free.x, free.y, h, w), true)));
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Splits the leftover of the `free` space after placing `used` at its top-left.
+    fn split_free(&mut self, free: &Rect, used: &Rect) {
+        let leftover_w = free.w - used.w;
+        let leftover_h = free.h - used.h;
+
+        let horizontal = match self.split {
+            SplitRule::ShorterLeftoverAxis => leftover_w <= leftover_h,
+            SplitRule::LongerLeftoverAxis => leftover_w > leftover_h,
+            SplitRule::MinimizeArea => {
+                used.w as u64 * leftover_h as u64 > leftover_w as u64 * used.h as u64
+            }
+            SplitRule::MaximizeArea => {
+                used.w as u64 * leftover_h as u64 <= leftover_w as u64 * used.h as u64
+            }
+            SplitRule::ShorterAxis => free.w <= free.h,
+            SplitRule::LongerAxis => free.w > free.h,
+        };
+
+        let (below_w, right_h) = if horizontal {
+            (free.w, used.h)
+        } else {
+            (used.w, free.h)
+        };
+
+        let below = Rect::new(free.x, free.y + used.h, below_w, leftover_h);
+        let right = Rect::new(free.x + used.w, free.y, leftover_w, right_h);
+
+        for rect in [below, right] {
+            if rect.area() > 0 {
+                self.free.push(rect);
+            }
+        }
+    }
+
+    /// Places a rectangle of (w, h) without any [`PackerConfig`] padding, border or extrusion.
+    pub(crate) fn place(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        let (i, rect) = self.find_position(w, h)?;
+
+        let free = self.free.swap_remove(i);
+        self.split_free(&free, &rect);
+
+        if self.merge {
+            merge_rects(&mut self.free);
+        }
+
+        self.used_area.expand_with(&rect);
+
+        Some(rect)
+    }
+}
+
+impl DynamicPacker for GuillotinePacker {
+    fn remove(&mut self, rect: &Rectf) {
+        self.free.push(self.config.slot_rect(rect));
+        // always merge, otherwise the space would be fragmented forever
+        merge_rects(&mut self.free);
+    }
+}
+
+impl Packer for GuillotinePacker {
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
+        let rect = self.place(w, h)?;
+        Some(self.config.content_rect(rect))
+    }
+
+    fn reset(&mut self, resize: Option<Size>) {
+        if let Some(Size { w, h }) = resize {
+            self.config.max_width = w;
+            self.config.max_height = h;
+        }
+        let bin = self.config.bin_size();
+        self.used_area = Size::ZERO;
+        self.free.clear();
+        self.free.push(Rect::new(0, 0, bin.w, bin.h));
+    }
+
+    fn grow(&mut self, size: Size) {
+        let (old, new) = self.config.grow(size);
+        if new.w > old.w {
+            self.free.push(Rect::new(old.w, 0, new.w - old.w, new.h));
+        }
+        if new.h > old.h {
+            self.free.push(Rect::new(0, old.h, old.w, new.h - old.h));
+        }
+        merge_rects(&mut self.free);
+    }
+
+    fn used_area(&self) -> Size {
+        self.config.atlas_size(self.used_area)
+    }
+}
@@ -0,0 +1,193 @@
+// TexturePacker JSON format, as loaded by Phaser, PixiJS and most engines, the `frame` has the size of the
+// sprite before the rotation and rotated sprites are turned 90 degrees clockwise inside the atlas
+
+use crate::{RectOutput, Size, Trim};
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::Serialize;
+
+/// Layout of the frames of a TexturePacker JSON file.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum JsonFormat {
+    /// `frames` is an object indexed by the frame name (JSON-Hash).
+    #[default]
+    Hash,
+    /// `frames` is an array, each frame has its name in `filename` (JSON-Array).
+    Array,
+}
+
+/// Configuration of [`to_texture_packer_json`].
+#[derive(Debug, Clone)]
+pub struct JsonConfig {
+    /// Layout of the frames. Default value is [`JsonFormat::Hash`].
+    pub format: JsonFormat,
+    /// File name of the image of each atlas written in the `meta`, `{n}` is replaced by the index of the
+    /// atlas. Default value is `"atlas{n}.png"`.
+    pub image: String,
+    /// Pixel format written in the `meta`. Default value is `"RGBA8888"`.
+    pub pixel_format: String,
+}
+
+impl Default for JsonConfig {
+    fn default() -> Self {
+        Self {
+            format: JsonFormat::Hash,
+            image: "atlas{n}.png".to_string(),
+            pixel_format: "RGBA8888".to_string(),
+        }
+    }
+}
+
+/// Problem found by [`to_texture_packer_json`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    /// Output with the same key of another one in its atlas, written with [`JsonFormat::Hash`] only one of
+    /// them would be kept. It's the index inside the `outputs` of the second one.
+    DuplicateName { output: usize },
+}
+
+#[derive(Serialize)]
+struct JsonRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+struct JsonSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonFrame<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<&'a str>,
+    frame: JsonRect,
+    rotated: bool,
+    trimmed: bool,
+    sprite_source_size: JsonRect,
+    source_size: JsonSize,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum JsonFrames<'a> {
+    Hash(BTreeMap<&'a str, JsonFrame<'a>>),
+    Array(Vec<JsonFrame<'a>>),
+}
+
+#[derive(Serialize)]
+struct JsonMeta {
+    app: &'static str,
+    version: &'static str,
+    image: String,
+    format: String,
+    size: JsonSize,
+    scale: &'static str,
+}
+
+#[derive(Serialize)]
+struct JsonAtlas<'a> {
+    frames: JsonFrames<'a>,
+    meta: JsonMeta,
+}
+
+/// Writes a TexturePacker JSON file for each atlas of `atlas_sizes`, with a frame for each of the `outputs`
+/// inside it named by its key.
+///
+/// `trim` gives the [`Trim`] of the sprites that were trimmed before packing, for the `trimmed`,
+/// `spriteSourceSize` and `sourceSize` of their frame; return `None` for the ones packed whole.
+///
+/// With [`JsonFormat::Hash`] the keys must be unique inside each atlas, otherwise it fails with
+/// [`JsonError::DuplicateName`].
+pub fn to_texture_packer_json<K: AsRef<str>>(
+    outputs: &[RectOutput<K>],
+    atlas_sizes: &[Size],
+    config: &JsonConfig,
+    mut trim: impl FnMut(&K) -> Option<Trim>,
+) -> Result<Vec<String>, JsonError> {
+    atlas_sizes
+        .iter()
+        .enumerate()
+        .map(|(atlas, size)| {
+            let frames = outputs
+                .iter()
+                .enumerate()
+                .filter(|(_, output)| output.atlas == atlas)
+                .map(|(i, output)| {
+                    let rect = &output.rect;
+                    let mut packed = rect.size();
+                    if rect.flipped {
+                        packed.flip();
+                    }
+                    let trim = trim(&output.key).unwrap_or(Trim::none(packed));
+
+                    let frame = JsonFrame {
+                        filename: None,
+                        frame: JsonRect {
+                            x: rect.x,
+                            y: rect.y,
+                            w: packed.w,
+                            h: packed.h,
+                        },
+                        rotated: rect.flipped,
+                        trimmed: trim.is_trimmed(),
+                        sprite_source_size: JsonRect {
+                            x: trim.rect.x,
+                            y: trim.rect.y,
+                            w: packed.w,
+                            h: packed.h,
+                        },
+                        source_size: JsonSize {
+                            w: trim.source_size.w,
+                            h: trim.source_size.h,
+                        },
+                    };
+                    (i, output.key.as_ref(), frame)
+                });
+
+            let frames = match config.format {
+                JsonFormat::Hash => {
+                    let mut hash = BTreeMap::new();
+                    for (i, name, frame) in frames {
+                        if hash.insert(name, frame).is_some() {
+                            return Err(JsonError::DuplicateName { output: i });
+                        }
+                    }
+                    JsonFrames::Hash(hash)
+                }
+                JsonFormat::Array => JsonFrames::Array(
+                    frames
+                        .map(|(_, name, frame)| JsonFrame {
+                            filename: Some(name),
+                            ..frame
+                        })
+                        .collect(),
+                ),
+            };
+
+            let json = JsonAtlas {
+                frames,
+                meta: JsonMeta {
+                    app: env!("CARGO_PKG_REPOSITORY"),
+                    version: "1.0",
+                    image: config.image.replace("{n}", &atlas.to_string()),
+                    format: config.pixel_format.clone(),
+                    size: JsonSize {
+                        w: size.w,
+                        h: size.h,
+                    },
+                    scale: "1",
+                },
+            };
+            // only strings, numbers and booleans, serializing can't fail
+            Ok(serde_json::to_string_pretty(&json).unwrap())
+        })
+        .collect()
+}
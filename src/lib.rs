@@ -3,18 +3,66 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 use alloc::{vec, vec::Vec};
 use core::cmp::Ordering;
 
-pub use skyline_packer::SkylinePacker;
+#[cfg(feature = "image")]
+pub use image;
+
+pub use anneal::{pack_annealing, AnnealConfig};
+pub use bucketed_shelf_allocator::{AllocId, Allocation, BucketedShelfAllocator};
+pub use buddy_packer::BuddyPacker;
+#[cfg(feature = "image")]
+pub use compose::{compose, compose_trimmed, ComposeConfig, ComposeError};
+#[cfg(feature = "image")]
+pub use dedup::{content_hash, same_content};
+pub use dedup::{dedup, dedup_by, Dedup};
+pub use gdx_atlas::{
+    parse_gdx_atlas, to_gdx_atlas, GdxAtlas, GdxConfig, GdxError, GdxPage, GdxRegion,
+};
+pub use guillotine_packer::{FreeRectChoice, GuillotinePacker, SplitRule};
+#[cfg(feature = "json")]
+pub use json::{to_texture_packer_json, JsonConfig, JsonError, JsonFormat};
+pub use maxrects_packer::{MaxRectsHeuristic, MaxRectsPacker};
+pub use multi_atlas::{pack_multi, pack_multi_with, AtlasChoice, MultiAtlasConfig};
+pub use optimal::{pack_optimal, OptimalConfig, OptimalResult};
+pub use optimize::pack_with_best_size;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_pack, par_pack_with, par_pack_with_best_size, ParRectOrdering};
+pub use shelf_packer::{ShelfChoice, ShelfPacker};
+pub use skyline_packer::{SkylineHeuristic, SkylinePacker};
 pub use split_packer::SplitPacker;
 pub use strip_packer::StripPacker;
-
-//mod optimize;
+pub use trim::Trim;
+#[cfg(feature = "image")]
+pub use trim::{trim, trim_inputs, TrimConfig};
+pub use validate::{validate, ValidationError};
+
+mod anneal;
+mod bucketed_shelf_allocator;
+mod buddy_packer;
+#[cfg(feature = "image")]
+mod compose;
+mod dedup;
+mod gdx_atlas;
+mod guillotine_packer;
+#[cfg(feature = "json")]
+mod json;
+mod maxrects_packer;
+mod multi_atlas;
+mod optimal;
+mod optimize;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod shelf_packer;
 mod skyline_packer;
 mod split_packer;
 mod strip_packer;
+mod trim;
+mod validate;
 
 /// Configuration for a texture packer.
 #[derive(Debug, Copy, Clone)]
@@ -26,6 +74,13 @@ pub struct PackerConfig {
     /// True to allow rotation of the input images. Default value is `true`. Images rotated will be
     /// rotated 90 degrees clockwise.
     pub allow_flipping: bool,
+    /// Gap in pixels between two rectangles. Default value is `0`.
+    pub padding: u32,
+    /// Margin in pixels between the rectangles and the edges of the packed image. Default value is `0`.
+    pub border: u32,
+    /// Pixels reserved around each rectangle to duplicate its edges, so texture filtering doesn't bleed
+    /// neighbouring colors inside it. Default value is `0`.
+    pub extrude: u32,
 }
 
 impl Default for PackerConfig {
@@ -34,12 +89,89 @@ impl Default for PackerConfig {
             max_width: 1024,
             max_height: 1024,
             allow_flipping: true,
+            padding: 0,
+            border: 0,
+            extrude: 0,
+        }
+    }
+}
+
+impl PackerConfig {
+    /// Space available for the packers to place the rectangles, the `border` is excluded but the
+    /// `padding` of the last rectangle of each row or column is allowed to go over it. It's clamped so
+    /// the atlas, borders included, still fits in an `u32`.
+    pub(crate) fn bin_size(&self) -> Size {
+        let border = 2 * self.border as u64;
+        let side = |max: u32| {
+            let side = (max as u64 + self.padding as u64).saturating_sub(border);
+            side.min((u32::MAX as u64).saturating_sub(border)) as u32
+        };
+        Size {
+            w: side(self.max_width),
+            h: side(self.max_height),
+        }
+    }
+
+    /// Space reserved inside the bin for a rectangle of (w, h) with its extrusion and padding, `None`
+    /// when it doesn't fit in an `u32`.
+    #[inline(always)]
+    pub(crate) fn slot_size(&self, w: u32, h: u32) -> Option<(u32, u32)> {
+        let extra = self.extrude.checked_mul(2)?.checked_add(self.padding)?;
+        Some((w.checked_add(extra)?, h.checked_add(extra)?))
+    }
+
+    /// Converts a slot placed inside the bin to the rectangle content in atlas coordinates.
+    #[inline(always)]
+    pub(crate) fn content_rect(&self, slot: Rectf) -> Rectf {
+        // the slot is at least as big as its extrusion and the bin leaves room for the borders, so
+        // adding them in this order can't overflow
+        let extra = 2 * self.extrude + self.padding;
+        Rectf {
+            x: slot.x + self.extrude + self.border,
+            y: slot.y + self.extrude + self.border,
+            w: slot.w - extra,
+            h: slot.h - extra,
+            flipped: slot.flipped,
+        }
+    }
+
+    /// Enlarges `max_width` and `max_height` to `size`, returns the bin size before and after it.
+    pub(crate) fn grow(&mut self, size: Size) -> (Size, Size) {
+        let old = self.bin_size();
+        self.max_width = self.max_width.max(size.w);
+        self.max_height = self.max_height.max(size.h);
+        (old, self.bin_size())
+    }
+
+    /// Inverse of [`PackerConfig::content_rect`], gives back the slot reserved for a rectangle. Rectangles
+    /// that weren't placed by the packer saturate instead of overflowing, they never match a slot.
+    #[inline(always)]
+    pub(crate) fn slot_rect(&self, content: &Rectf) -> Rect {
+        let offset = self.border.saturating_add(self.extrude);
+        let extra = self.extrude.saturating_mul(2).saturating_add(self.padding);
+        Rect {
+            x: content.x.saturating_sub(offset),
+            y: content.y.saturating_sub(offset),
+            w: content.w.saturating_add(extra),
+            h: content.h.saturating_add(extra),
+        }
+    }
+
+    /// Converts the area used by the slots inside the bin to the atlas area, borders included.
+    pub(crate) fn atlas_size(&self, used: Size) -> Size {
+        if used.w == 0 || used.h == 0 {
+            return Size::ZERO;
+        }
+        let border = self.border.saturating_mul(2);
+        Size {
+            w: (used.w - self.padding).saturating_add(border),
+            h: (used.h - self.padding).saturating_add(border),
         }
     }
 }
 
 /// Defines a rectangle in pixels with the origin at the top-left of the texture atlas.
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(C)]
 pub struct Rect {
     pub x: u32,
@@ -50,7 +182,7 @@ pub struct Rect {
 
 impl Rect {
     /// Create a new [Rect] based on a position and its width and height.
-    pub fn new(x: u32, y: u32, w: u32, h: u32) -> Rect {
+    pub const fn new(x: u32, y: u32, w: u32, h: u32) -> Rect {
         Rect { x, y, w, h }
     }
 
@@ -98,10 +230,40 @@ impl Rect {
             && self.top() <= other.top()
             && self.bottom() >= other.bottom()
     }
+
+    /// Joins two rectangles that share a whole edge.
+    pub(crate) fn merged(&self, other: &Rect) -> Option<Rect> {
+        let (a, b) = (self, other);
+        if a.y == b.y && a.h == b.h && (a.x + a.w == b.x || b.x + b.w == a.x) {
+            Some(Rect::new(a.x.min(b.x), a.y, a.w + b.w, a.h))
+        } else if a.x == b.x && a.w == b.w && (a.y + a.h == b.y || b.y + b.h == a.y) {
+            Some(Rect::new(a.x, a.y.min(b.y), a.w, a.h + b.h))
+        } else {
+            None
+        }
+    }
+}
+
+/// Merges every pair of `rects` that share a whole edge, until no more merges are possible.
+pub(crate) fn merge_rects(rects: &mut Vec<Rect>) {
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..rects.len() {
+            for j in (i + 1)..rects.len() {
+                if let Some(rect) = rects[i].merged(&rects[j]) {
+                    rects[i] = rect;
+                    rects.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
 }
 
 /// [`Rect`] that could be flipped sideway (rotated by 90 degrees clockwise)
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(C)]
 pub struct Rectf {
     pub x: u32,
@@ -132,7 +294,7 @@ impl core::ops::Deref for Rectf {
     }
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Size {
     pub w: u32,
     pub h: u32,
@@ -184,12 +346,31 @@ impl Size {
     }
 }
 
+/// Every packer must respect the [`PackerConfig::padding`], [`PackerConfig::border`] and
+/// [`PackerConfig::extrude`], the returned [`Rectf`] only describes the content area.
 pub trait Packer {
+    /// Places a `w` by `h` rectangle, `None` when there is no room left for it.
+    /// Empty rectangles, with a zero width or height, are never placed and always give `None`.
     fn insert(&mut self, w: u32, h: u32) -> Option<Rectf>;
     fn reset(&mut self, resize: Option<Size>);
+    /// Enlarges the atlas to `size` keeping every rectangle already placed where it is,
+    /// so the old contents can be copied to the top-left of the new atlas.
+    ///
+    /// Dimensions smaller than the current ones are ignored.
+    fn grow(&mut self, size: Size);
     fn used_area(&self) -> Size;
 }
 
+/// Packers that can give back the space of a single rectangle without a [`Packer::reset`],
+/// useful for dynamic atlases like glyph caches.
+pub trait DynamicPacker: Packer {
+    /// Frees the space of a rectangle returned by [`Packer::insert`], the rectangle itself works
+    /// as the allocation handle. Removing a rectangle that isn't allocated is a logic error.
+    ///
+    /// The [`Packer::used_area`] isn't shrunk.
+    fn remove(&mut self, rect: &Rectf);
+}
+
 #[derive(Clone, Copy)]
 pub struct RectInput<K> {
     pub size: Size,
@@ -209,62 +390,86 @@ pub const RECT_SORT_FUNCTIONS: [fn(Size, Size) -> Ordering; 6] = [
     |a: Size, b: Size| (b.w.max(b.h)).cmp(&(a.w.max(a.h))),
     |a: Size, b: Size| b.w.cmp(&a.w),
     |a: Size, b: Size| b.h.cmp(&a.h),
-    |a: Size, b: Size| {
-        if b.pathological_mult() < a.pathological_mult() {
-            Ordering::Less
-        } else {
-            Ordering::Greater
-        }
-    },
+    |a: Size, b: Size| b.pathological_mult().total_cmp(&a.pathological_mult()),
 ];
 
+/// Result of [`pack`] and [`pack_multi`].
+#[derive(Clone)]
+pub struct PackResult<K> {
+    /// Packed rectangles, in the same order of the inputs.
+    pub rects: Vec<RectOutput<K>>,
+    /// Keys of the inputs that couldn't fit even inside an empty atlas, in the same order of the inputs.
+    pub unfit: Vec<K>,
+    /// Keys of the inputs left out once [`MultiAtlasConfig::max_atlases`] was reached, in the same order
+    /// of the inputs.
+    pub overflow: Vec<K>,
+    /// Size of each atlas, borders included, indexed by [`RectOutput::atlas`].
+    pub atlas_sizes: Vec<Size>,
+}
+
+impl<K> Default for PackResult<K> {
+    fn default() -> Self {
+        Self {
+            rects: vec![],
+            unfit: vec![],
+            overflow: vec![],
+            atlas_sizes: vec![],
+        }
+    }
+}
+
+impl<K> PackResult<K> {
+    /// True when every input was packed.
+    pub fn is_complete(&self) -> bool {
+        self.unfit.is_empty() && self.overflow.is_empty()
+    }
+
+    /// Sorts the rectangles by atlas, the ones inside the same atlas keep the input order.
+    pub fn sort_by_atlas(&mut self) {
+        self.rects.sort_by_key(|rect| rect.atlas);
+    }
+}
+
+/// Comparator used to sort the inputs before packing them, see [`pack_with`].
+pub type RectOrdering<K> = dyn Fn(&RectInput<K>, &RectInput<K>) -> Ordering;
+
 /// Sorts the input data using the heuristics defined in [`RECT_SORT_FUNCTIONS`] to find the best possible packing,
 /// the results might end up been inside multiple atlases.
 ///
-/// The output is sorted by atlas.
-pub fn pack<P: Packer, K: Copy>(
-    inputs: &mut Vec<RectInput<K>>,
+/// Inputs that don't fit inside the current atlas are retried on a new one, the ones that don't fit even
+/// inside an empty atlas are reported in [`PackResult::unfit`] without closing the current atlas, like the
+/// empty ones (with a zero width or height).
+///
+/// The `inputs` aren't modified, the output keeps their order, use [`PackResult::sort_by_atlas`] to get
+/// them sorted by atlas.
+pub fn pack<P: Packer + Clone, K: Copy>(inputs: &[RectInput<K>], packer: P) -> PackResult<K> {
+    let orderings = RECT_SORT_FUNCTIONS
+        .map(|cmp| move |a: &RectInput<K>, b: &RectInput<K>| (cmp)(a.size, b.size));
+    let orderings = orderings.each_ref().map(|cmp| cmp as &RectOrdering<K>);
+    pack_with(inputs, packer, &orderings)
+}
+
+/// Same as [`pack`] but sorts the input data using the given `orderings` instead of [`RECT_SORT_FUNCTIONS`],
+/// each ordering is a packing attempt and the one that uses less area wins.
+///
+/// With no orderings the input data is packed in the order it was given.
+pub fn pack_with<P: Packer + Clone, K: Copy>(
+    inputs: &[RectInput<K>],
     mut packer: P,
-) -> Vec<RectOutput<K>> {
-    let mut output = vec![];
-    let mut output_area = core::u64::MAX;
-
-    let mut current = vec![];
-    let mut current_area;
-
-    for cmp in RECT_SORT_FUNCTIONS {
-        current.clear();
-        current_area = 0;
-
-        inputs.sort_by(|a, b| (cmp)(a.size, b.size));
-        let mut iterator = inputs.iter();
-
-        // use as many atlas as needed
-        let mut atlas = 0;
-        'atlasing: loop {
-            packer.reset(None);
-
-            loop {
-                if let Some(input) = iterator.next() {
-                    if let Some(rect) = packer.insert(input.size.w, input.size.h) {
-                        current.push(RectOutput {
-                            rect,
-                            atlas,
-                            key: input.key,
-                        });
-                    } else {
-                        // use another atlas
-                        current_area += packer.used_area().area();
-                        atlas += 1;
-                        break;
-                    }
-                } else {
-                    break 'atlasing;
-                }
-            }
-        }
+    orderings: &[&RectOrdering<K>],
+) -> PackResult<K> {
+    // the inputs are sorted through their indices
+    let mut order = vec![];
 
-        current_area += packer.used_area().area();
+    let mut output = Packing::default();
+    let mut output_area = u64::MAX;
+
+    let mut current = Packing::default();
+
+    for i in 0..orderings.len().max(1) {
+        sort_order(inputs, &mut order, orderings.get(i).copied());
+
+        let current_area = pack_order(inputs, &order, &mut packer, &mut current);
 
         if current_area < output_area {
             output_area = current_area;
@@ -272,5 +477,128 @@ pub fn pack<P: Packer, K: Copy>(
         }
     }
 
-    output
+    pack_result(inputs, output)
+}
+
+/// Indices of the `inputs` sorted by `cmp`, inputs that compare equal keep their order.
+pub(crate) fn sort_order<K, F>(inputs: &[RectInput<K>], order: &mut Vec<usize>, cmp: Option<&F>)
+where
+    F: Fn(&RectInput<K>, &RectInput<K>) -> Ordering + ?Sized,
+{
+    order.clear();
+    order.extend(0..inputs.len());
+    if let Some(cmp) = cmp {
+        order.sort_by(|&a, &b| cmp(&inputs[a], &inputs[b]));
+    }
+}
+
+/// Placement of the inputs of a packing attempt.
+#[derive(Clone, Default)]
+pub(crate) struct Packing {
+    /// Placement and atlas of each input, indexed like the inputs.
+    pub placements: Vec<Option<(Rectf, usize)>>,
+    /// Used area of each atlas.
+    pub atlas_sizes: Vec<Size>,
+}
+
+/// Packs the inputs in the given `order` using as many atlases as needed, fills the `packing`
+/// and returns the area used by all the atlases.
+pub(crate) fn pack_order<P: Packer + Clone, K>(
+    inputs: &[RectInput<K>],
+    order: &[usize],
+    packer: &mut P,
+    packing: &mut Packing,
+) -> u64 {
+    let Packing {
+        placements,
+        atlas_sizes,
+    } = packing;
+    placements.clear();
+    placements.resize(inputs.len(), None);
+    atlas_sizes.clear();
+    let mut area = 0;
+
+    // use as many atlas as needed
+    let mut atlas = 0;
+    let mut atlas_empty = true;
+    packer.reset(None);
+    // empty atlas used to check the inputs that don't fit the current one, made when first needed
+    let mut empty: Option<P> = None;
+
+    for &index in order {
+        let input = &inputs[index];
+        // empty rectangles are never placed, they are left as `None`
+        if input.size.area() == 0 {
+            continue;
+        }
+        let mut inserted = packer.insert(input.size.w, input.size.h);
+
+        if inserted.is_none() && !atlas_empty {
+            let empty = empty.get_or_insert_with(|| {
+                let mut empty = packer.clone();
+                empty.reset(None);
+                empty
+            });
+            let fits = empty.insert(input.size.w, input.size.h).is_some();
+            empty.reset(None);
+
+            // when not even an empty atlas can hold it, the current atlas is kept open
+            if fits {
+                // use another atlas
+                atlas_sizes.push(packer.used_area());
+                area += packer.used_area().area();
+                atlas += 1;
+                atlas_empty = true;
+                packer.reset(None);
+
+                inserted = packer.insert(input.size.w, input.size.h);
+            }
+        }
+
+        // when not even an empty atlas can hold it, it's left as `None`
+        if let Some(rect) = inserted {
+            atlas_empty = false;
+            placements[index] = Some((rect, atlas));
+        }
+    }
+
+    if !atlas_empty {
+        atlas_sizes.push(packer.used_area());
+    }
+    area + packer.used_area().area()
+}
+
+/// Builds the [`PackResult`] from the placement of each input.
+pub(crate) fn pack_result<K: Copy>(inputs: &[RectInput<K>], packing: Packing) -> PackResult<K> {
+    let Packing {
+        placements,
+        atlas_sizes,
+    } = packing;
+    let mut result = PackResult {
+        atlas_sizes,
+        ..PackResult::default()
+    };
+    for (input, placement) in inputs.iter().zip(&placements) {
+        match *placement {
+            Some((rect, atlas)) => result.rects.push(RectOutput {
+                rect,
+                atlas,
+                key: input.key,
+            }),
+            None => result.unfit.push(input.key),
+        }
+    }
+
+    #[cfg(feature = "debug-validate")]
+    {
+        let expected: Vec<_> = inputs
+            .iter()
+            .zip(&placements)
+            .filter(|(_, placement)| placement.is_some())
+            .map(|(input, _)| Some(input.size))
+            .collect();
+        validate::assert_valid(&result.rects, &expected, &result.atlas_sizes);
+    }
+
+    result
 }
@@ -0,0 +1,279 @@
+// based on: RectangleBinPack https://github.com/juj/RectangleBinPack
+// paper: "A Thousand Ways to Pack the Bin" by Jukka Jylänki
+
+use crate::{DynamicPacker, Packer, PackerConfig, Rect, Rectf, Size};
+use alloc::{vec, vec::Vec};
+
+/// Rule used by [`MaxRectsPacker`] to choose where each rectangle goes.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MaxRectsHeuristic {
+    /// Best Short Side Fit, positions the rectangle against the free space that leaves the smallest leftover
+    /// on its shorter side.
+    #[default]
+    BestShortSideFit,
+    /// Best Long Side Fit, positions the rectangle against the free space that leaves the smallest leftover
+    /// on its longer side.
+    BestLongSideFit,
+    /// Best Area Fit, positions the rectangle inside the smallest free space it fits.
+    BestAreaFit,
+    /// Bottom-Left rule, the Tetris placement, keeps the rectangle bottom as close to the top as possible.
+    BottomLeft,
+    /// Contact Point rule, chooses the position where the rectangle touches the most other rectangles
+    /// or atlas edges.
+    ContactPoint,
+}
+
+/// Keeps a list of maximal free rectangles, every free space is as big as it can be
+/// so they might overlap each other.
+///
+/// *Gives the best packing density with unsorted input data*, but it's slower than the other packers
+#[derive(Clone)]
+pub struct MaxRectsPacker {
+    config: PackerConfig,
+    heuristic: MaxRectsHeuristic,
+    free: Vec<Rect>,
+    /// Needed by the [`MaxRectsHeuristic::ContactPoint`].
+    used: Vec<Rect>,
+    used_area: Size,
+}
+
+impl MaxRectsPacker {
+    pub fn new(config: PackerConfig) -> Self {
+        let bin = config.bin_size();
+        Self {
+            config,
+            heuristic: MaxRectsHeuristic::default(),
+            free: vec![Rect::new(0, 0, bin.w, bin.h)],
+            used: vec![],
+            used_area: Size::ZERO,
+        }
+    }
+
+    /// Changes the heuristic used to place the rectangles.
+    pub fn with_heuristic(mut self, heuristic: MaxRectsHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    pub const fn heuristic(&self) -> MaxRectsHeuristic {
+        self.heuristic
+    }
+
+    /// How well a rectangle of (w, h) fits at the top-left of the `free` space, lower scores are better.
+    fn score(&self, free: &Rect, w: u32, h: u32) -> (u64, u64) {
+        let leftover_w = (free.w - w) as u64;
+        let leftover_h = (free.h - h) as u64;
+        let short_side = leftover_w.min(leftover_h);
+        let long_side = leftover_w.max(leftover_h);
+
+        match self.heuristic {
+            MaxRectsHeuristic::BestShortSideFit => (short_side, long_side),
+            MaxRectsHeuristic::BestLongSideFit => (long_side, short_side),
+            MaxRectsHeuristic::BestAreaFit => (free.area() - w as u64 * h as u64, short_side),
+            MaxRectsHeuristic::BottomLeft => ((free.y + h) as u64, free.x as u64),
+            MaxRectsHeuristic::ContactPoint => {
+                // more contact is better
+                let contact = self.contact_score(&Rect::new(free.x, free.y, w, h));
+                (u64::MAX - contact, 0)
+            }
+        }
+    }
+
+    /// Total length of the `rect` edges touching the atlas edges or other rectangles.
+    fn contact_score(&self, rect: &Rect) -> u64 {
+        let bin = self.config.bin_size();
+        let mut score = 0;
+
+        if rect.x == 0 || rect.x + rect.w == bin.w {
+            score += rect.h as u64;
+        }
+        if rect.y == 0 || rect.y + rect.h == bin.h {
+            score += rect.w as u64;
+        }
+
+        for used in &self.used {
+            if used.x == rect.x + rect.w || used.x + used.w == rect.x {
+                score += common_interval(used.y, used.y + used.h, rect.y, rect.y + rect.h) as u64;
+            }
+            if used.y == rect.y + rect.h || used.y + used.h == rect.y {
+                score += common_interval(used.x, used.x + used.w, rect.x, rect.x + rect.w) as u64;
+            }
+        }
+
+        score
+    }
+
+    fn find_position(&self, w: u32, h: u32) -> Option<Rectf> {
+        let mut best_score = (u64::MAX, u64::MAX);
+        let mut best = None;
+
+        for free in &self.free {
+            if free.w >= w && free.h >= h {
+                let score = self.score(free, w, h);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some(Rectf::from_rect(Rect::new(free.x, free.y, w, h), false));
+                }
+            }
+
+            if self.config.allow_flipping && w != h && free.w >= h && free.h >= w {
+                let score = self.score(free, h, w);
+                if best.is_none() || score < best_score {
+                    best_score = score;
+                    best = Some(Rectf::from_rect(Rect::new(free.x, free.y, h, w), true));
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Removes the `used` area from every free space that overlaps it, replacing them with the maximal
+    /// spaces left around it.
+    fn split_free(&mut self, used: &Rect) {
+        let mut i = 0;
+        let count = self.free.len();
+        while i < count {
+            let free = self.free[i];
+            if !intersects(&free, used) {
+                i += 1;
+                continue;
+            }
+
+            // left side
+            if used.x > free.x {
+                self.free
+                    .push(Rect::new(free.x, free.y, used.x - free.x, free.h));
+            }
+            // right side
+            if used.x + used.w < free.x + free.w {
+                let x = used.x + used.w;
+                self.free
+                    .push(Rect::new(x, free.y, free.x + free.w - x, free.h));
+            }
+            // top side
+            if used.y > free.y {
+                self.free
+                    .push(Rect::new(free.x, free.y, free.w, used.y - free.y));
+            }
+            // bottom side
+            if used.y + used.h < free.y + free.h {
+                let y = used.y + used.h;
+                self.free
+                    .push(Rect::new(free.x, y, free.w, free.y + free.h - y));
+            }
+
+            // mark as degenerated, it will be removed by `prune_free`
+            self.free[i].w = 0;
+            i += 1;
+        }
+    }
+
+    /// Computes the free spaces from scratch using the placed rectangles, so they stay maximal.
+    fn rebuild_free(&mut self) {
+        let bin = self.config.bin_size();
+        self.free.clear();
+        self.free.push(Rect::new(0, 0, bin.w, bin.h));
+        for i in 0..self.used.len() {
+            let used = self.used[i];
+            self.split_free(&used);
+            self.prune_free();
+        }
+    }
+
+    /// Removes the degenerated free spaces and the ones contained by others.
+    fn prune_free(&mut self) {
+        self.free.retain(|free| free.w > 0 && free.h > 0);
+
+        let mut i = 0;
+        while i < self.free.len() {
+            let mut j = i + 1;
+            let mut removed = false;
+            while j < self.free.len() {
+                if contains(&self.free[j], &self.free[i]) {
+                    self.free.swap_remove(i);
+                    removed = true;
+                    break;
+                }
+                if contains(&self.free[i], &self.free[j]) {
+                    self.free.swap_remove(j);
+                } else {
+                    j += 1;
+                }
+            }
+            if !removed {
+                i += 1;
+            }
+        }
+    }
+}
+
+impl DynamicPacker for MaxRectsPacker {
+    fn remove(&mut self, rect: &Rectf) {
+        let slot = self.config.slot_rect(rect);
+        if let Some(i) = self
+            .used
+            .iter()
+            .position(|used| used.x == slot.x && used.y == slot.y)
+        {
+            self.used.swap_remove(i);
+        }
+
+        self.rebuild_free();
+    }
+}
+
+impl Packer for MaxRectsPacker {
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
+        let rect = self.find_position(w, h)?;
+
+        self.split_free(&rect);
+        self.prune_free();
+        self.used.push(*rect);
+        self.used_area.expand_with(&rect);
+
+        Some(self.config.content_rect(rect))
+    }
+
+    fn reset(&mut self, resize: Option<Size>) {
+        if let Some(Size { w, h }) = resize {
+            self.config.max_width = w;
+            self.config.max_height = h;
+        }
+        let bin = self.config.bin_size();
+        self.used_area = Size::ZERO;
+        self.used.clear();
+        self.free.clear();
+        self.free.push(Rect::new(0, 0, bin.w, bin.h));
+    }
+
+    fn grow(&mut self, size: Size) {
+        self.config.grow(size);
+        self.rebuild_free();
+    }
+
+    fn used_area(&self) -> Size {
+        self.config.atlas_size(self.used_area)
+    }
+}
+
+#[inline(always)]
+fn intersects(a: &Rect, b: &Rect) -> bool {
+    a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+}
+
+/// Same as [`Rect::contains`] but also works with empty rectangles.
+#[inline(always)]
+fn contains(a: &Rect, b: &Rect) -> bool {
+    a.x <= b.x && a.y <= b.y && a.x + a.w >= b.x + b.w && a.y + a.h >= b.y + b.h
+}
+
+/// Length of the overlap between the intervals `[a_start, a_end)` and `[b_start, b_end)`.
+#[inline(always)]
+fn common_interval(a_start: u32, a_end: u32, b_start: u32, b_end: u32) -> u32 {
+    a_end.min(b_end).saturating_sub(a_start.max(b_start))
+}
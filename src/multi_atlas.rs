@@ -0,0 +1,292 @@
+use crate::{
+    sort_order, PackResult, Packer, RectInput, RectOrdering, RectOutput, Rectf, Size,
+    RECT_SORT_FUNCTIONS,
+};
+use alloc::{vec, vec::Vec};
+
+/// Rule used by [`pack_multi`] to choose the atlas that receives each rectangle.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AtlasChoice {
+    /// First open atlas the rectangle fits, in the order they were opened.
+    #[default]
+    FirstFit,
+    /// Open atlas with the least free area the rectangle fits.
+    BestFit,
+}
+
+/// Configuration of [`pack_multi`].
+#[derive(Debug, Copy, Clone)]
+pub struct MultiAtlasConfig {
+    /// Rule used to choose between the open atlases. Default value is [`AtlasChoice::FirstFit`].
+    pub choice: AtlasChoice,
+    /// True to repack the rectangles once they are packed, so every atlas ends up with about the same
+    /// fill without using more atlases, the atlases are kept as they are when the rectangles don't fit
+    /// this way. Default value is `false`.
+    pub rebalance: bool,
+    /// Max number of atlases, the rectangles left without an atlas are reported in
+    /// [`PackResult::overflow`]. Default value is `None`.
+    pub max_atlases: Option<usize>,
+}
+
+impl Default for MultiAtlasConfig {
+    fn default() -> Self {
+        Self {
+            choice: AtlasChoice::FirstFit,
+            rebalance: false,
+            max_atlases: None,
+        }
+    }
+}
+
+/// Where each input ended up.
+#[derive(Clone, Copy)]
+enum Placement {
+    Packed(Rectf, usize),
+    /// Fits an empty atlas but every atlas allowed was full.
+    Overflow,
+    Unfit,
+}
+
+/// Open atlases used during a packing attempt.
+struct Atlases<P> {
+    /// Reset packer used to open the new atlases.
+    empty: P,
+    packers: Vec<P>,
+    /// Area of the rectangles inside each atlas.
+    fill: Vec<u64>,
+}
+
+impl<P: Packer + Clone> Atlases<P> {
+    fn new(mut packer: P) -> Self {
+        packer.reset(None);
+        Self {
+            empty: packer,
+            packers: vec![],
+            fill: vec![],
+        }
+    }
+
+    /// Closes every atlas and opens `count` empty ones.
+    fn clear(&mut self, count: usize) {
+        self.packers.clear();
+        self.packers.resize(count, self.empty.clone());
+        self.fill.clear();
+        self.fill.resize(count, 0);
+    }
+
+    /// Used area of each atlas.
+    fn sizes(&self) -> Vec<Size> {
+        self.packers
+            .iter()
+            .map(|packer| packer.used_area())
+            .collect()
+    }
+
+    /// Area used by every atlas.
+    fn area(&self) -> u64 {
+        self.packers
+            .iter()
+            .map(|packer| packer.used_area().area())
+            .sum()
+    }
+
+    /// Inserts a rectangle of (w, h) in one of the open atlases, the `open` ones are tried in order.
+    fn insert_open(&mut self, open: &[usize], w: u32, h: u32) -> Option<(Rectf, usize)> {
+        open.iter().find_map(|&atlas| {
+            let rect = self.packers[atlas].insert(w, h)?;
+            self.fill[atlas] += w as u64 * h as u64;
+            Some((rect, atlas))
+        })
+    }
+
+    /// Inserts a rectangle of (w, h) in a new atlas when `max_atlases` allows it.
+    fn insert_new(&mut self, max_atlases: Option<usize>, w: u32, h: u32) -> Placement {
+        let mut packer = self.empty.clone();
+        let Some(rect) = packer.insert(w, h) else {
+            return Placement::Unfit;
+        };
+        if max_atlases.is_some_and(|max| self.packers.len() >= max) {
+            return Placement::Overflow;
+        }
+
+        self.packers.push(packer);
+        self.fill.push(w as u64 * h as u64);
+        Placement::Packed(rect, self.packers.len() - 1)
+    }
+}
+
+/// Atlas order used for each insertion.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Fit {
+    First,
+    /// Fullest atlas first.
+    Best,
+    /// Emptiest atlas first, used to rebalance the atlases.
+    Worst,
+}
+
+/// Packs the inputs in the given `order` starting with `opened` empty atlases, returns the number of
+/// overflowed inputs.
+fn pack_atlases<P: Packer + Clone, K>(
+    atlases: &mut Atlases<P>,
+    inputs: &[RectInput<K>],
+    order: &[usize],
+    fit: Fit,
+    opened: usize,
+    max_atlases: Option<usize>,
+    placements: &mut [Placement],
+) -> usize {
+    atlases.clear(opened);
+
+    let mut open = vec![];
+    let mut overflow = 0;
+    for &index in order {
+        let Size { w, h } = inputs[index].size;
+
+        open.clear();
+        open.extend(0..atlases.packers.len());
+        match fit {
+            Fit::First => {}
+            Fit::Best => open.sort_by_key(|&atlas| core::cmp::Reverse(atlases.fill[atlas])),
+            Fit::Worst => open.sort_by_key(|&atlas| atlases.fill[atlas]),
+        }
+
+        placements[index] = match atlases.insert_open(&open, w, h) {
+            Some((rect, atlas)) => Placement::Packed(rect, atlas),
+            None => atlases.insert_new(max_atlases, w, h),
+        };
+        if let Placement::Overflow = placements[index] {
+            overflow += 1;
+        }
+    }
+
+    overflow
+}
+
+/// Same as [`pack`](crate::pack) but every open atlas is tried before opening a new one, so the last
+/// atlas isn't left almost empty.
+///
+/// The inputs are sorted using the heuristics defined in [`RECT_SORT_FUNCTIONS`], the attempt with less
+/// overflowed inputs, then less atlases, then less area wins.
+pub fn pack_multi<P: Packer + Clone, K: Copy>(
+    inputs: &[RectInput<K>],
+    packer: P,
+    config: MultiAtlasConfig,
+) -> PackResult<K> {
+    let orderings = RECT_SORT_FUNCTIONS
+        .map(|cmp| move |a: &RectInput<K>, b: &RectInput<K>| (cmp)(a.size, b.size));
+    let orderings = orderings.each_ref().map(|cmp| cmp as &RectOrdering<K>);
+    pack_multi_with(inputs, packer, config, &orderings)
+}
+
+/// Same as [`pack_multi`] but sorts the input data using the given `orderings`, like
+/// [`pack_with`](crate::pack_with).
+///
+/// With no orderings the input data is packed in the order it was given.
+pub fn pack_multi_with<P: Packer + Clone, K: Copy>(
+    inputs: &[RectInput<K>],
+    packer: P,
+    config: MultiAtlasConfig,
+    orderings: &[&RectOrdering<K>],
+) -> PackResult<K> {
+    let fit = match config.choice {
+        AtlasChoice::FirstFit => Fit::First,
+        AtlasChoice::BestFit => Fit::Best,
+    };
+    let mut atlases = Atlases::new(packer);
+
+    // the inputs are sorted through their indices
+    let mut order = vec![];
+
+    // (overflowed inputs, atlases, area) lower is better
+    let mut output = vec![Placement::Unfit; inputs.len()];
+    let mut output_score = (usize::MAX, usize::MAX, u64::MAX);
+    let mut output_sizes = vec![];
+
+    let mut current = vec![Placement::Unfit; inputs.len()];
+
+    for i in 0..orderings.len().max(1) {
+        sort_order(inputs, &mut order, orderings.get(i).copied());
+
+        let overflow = pack_atlases(
+            &mut atlases,
+            inputs,
+            &order,
+            fit,
+            0,
+            config.max_atlases,
+            &mut current,
+        );
+        let score = (overflow, atlases.packers.len(), atlases.area());
+
+        if score < output_score {
+            output_score = score;
+            core::mem::swap(&mut current, &mut output);
+            output_sizes = atlases.sizes();
+        }
+    }
+
+    if config.rebalance && output_score.1 > 1 {
+        // repack inside the same atlases always filling the emptiest one, the attempt with the smallest
+        // difference between the fullest and the emptiest atlas wins
+        let (overflow, count, _) = output_score;
+
+        // a rebalanced attempt is only kept when it beats the greedy packing
+        let mut fill = vec![0; count];
+        for (input, placement) in inputs.iter().zip(&output) {
+            if let Placement::Packed(_, atlas) = *placement {
+                fill[atlas] += input.size.area();
+            }
+        }
+        let mut best_spread = fill.iter().max().unwrap_or(&0) - fill.iter().min().unwrap_or(&0);
+
+        for i in 0..orderings.len().max(1) {
+            sort_order(inputs, &mut order, orderings.get(i).copied());
+
+            let rebalanced_overflow = pack_atlases(
+                &mut atlases,
+                inputs,
+                &order,
+                Fit::Worst,
+                count,
+                Some(count),
+                &mut current,
+            );
+            let spread =
+                atlases.fill.iter().max().unwrap_or(&0) - atlases.fill.iter().min().unwrap_or(&0);
+
+            if rebalanced_overflow <= overflow && spread < best_spread {
+                best_spread = spread;
+                core::mem::swap(&mut current, &mut output);
+                output_sizes = atlases.sizes();
+            }
+        }
+    }
+
+    let mut result = PackResult {
+        atlas_sizes: output_sizes,
+        ..PackResult::default()
+    };
+    #[cfg(feature = "debug-validate")]
+    let mut expected = vec![];
+    for (input, placement) in inputs.iter().zip(output) {
+        match placement {
+            Placement::Packed(rect, atlas) => {
+                result.rects.push(RectOutput {
+                    rect,
+                    atlas,
+                    key: input.key,
+                });
+                #[cfg(feature = "debug-validate")]
+                expected.push(Some(input.size));
+            }
+            Placement::Overflow => result.overflow.push(input.key),
+            Placement::Unfit => result.unfit.push(input.key),
+        }
+    }
+
+    #[cfg(feature = "debug-validate")]
+    crate::validate::assert_valid(&result.rects, &expected, &result.atlas_sizes);
+
+    result
+}
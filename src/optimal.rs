@@ -0,0 +1,518 @@
+// branch and bound using bottom-left fill: the lowest gap of the skyline is either filled by a rectangle
+// placed at its left or closed as waste. The atlas widths made of rectangle sides are tried from the widest
+// rectangle up to the widest atlas that could beat the heuristic packing, and the branches that can't beat
+// the best atlas found so far are pruned by the area left to place
+
+use crate::{
+    pack_with_best_size, MaxRectsPacker, PackerConfig, Rect, RectInput, RectOutput, Rectf, Size,
+};
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+/// Limits of [`pack_optimal`], once reached the best solution found so far is returned.
+#[derive(Debug, Copy, Clone)]
+pub struct OptimalConfig {
+    /// Max number of nodes of the search tree to visit. Default value is `10_000_000`.
+    pub max_nodes: u64,
+    /// Max time spent searching. Only checked with the `std` feature, without a clock it's ignored.
+    /// Default value is `None`.
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for OptimalConfig {
+    fn default() -> Self {
+        Self {
+            max_nodes: 10_000_000,
+            time_limit: None,
+        }
+    }
+}
+
+/// Result of [`pack_optimal`].
+#[derive(Clone)]
+pub struct OptimalResult<K> {
+    /// Packed rectangles, in the same order of the inputs.
+    pub rects: Vec<RectOutput<K>>,
+    /// Keys of the inputs left out, the ones that couldn't fit even inside the biggest atlas or every
+    /// input when the limits were reached before finding any packing.
+    pub unfit: Vec<K>,
+    /// Size of the atlas, borders included.
+    pub size: Size,
+    /// True when the search was completed, so no bottom-left fill packing uses a smaller atlas.
+    pub optimal: bool,
+}
+
+/// Slot of an input, with its padding and extrusion.
+#[derive(Clone, Copy)]
+struct Item {
+    w: u32,
+    h: u32,
+    /// The slot is the input turned, so the rectangles that only differ by their orientation are identical.
+    flipped: bool,
+    input: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Segment {
+    x: u32,
+    w: u32,
+    y: u32,
+}
+
+struct Search<'a> {
+    config: &'a PackerConfig,
+    /// Sorted by area, bigger first, so good solutions are found early.
+    items: Vec<Item>,
+    placed: Vec<Option<Rectf>>,
+    remaining: usize,
+    remaining_area: u64,
+    /// Width of the atlas being searched, in bin coordinates.
+    width: u32,
+    /// Smallest atlas the current width can give, its search ends once it's found.
+    width_bound: u64,
+    best: Vec<Option<Rectf>>,
+    best_area: u64,
+    nodes: u64,
+    max_nodes: u64,
+    #[cfg(feature = "std")]
+    deadline: Option<(std::time::Instant, Duration)>,
+    aborted: bool,
+    /// Buffer of [`Search::fillable_width`], kept between the nodes.
+    reachable: Vec<u64>,
+}
+
+impl Search<'_> {
+    /// Area of the atlas for a bin of (w, h).
+    fn atlas_area(&self, w: u32, h: u32) -> u64 {
+        self.config.atlas_size(Size::new(w, h)).area()
+    }
+
+    /// Biggest bin side, up to `max`, that with `other` on the other side can still beat the best atlas.
+    fn side_bound(&self, other: u32, max: u32) -> Option<u32> {
+        if self.best_area == u64::MAX {
+            return Some(max);
+        }
+
+        let atlas_other = self.config.atlas_size(Size::new(other, other)).w as u64;
+        let atlas_side = self.best_area.checked_sub(1)? / atlas_other.max(1);
+        let side =
+            (atlas_side + self.config.padding as u64).checked_sub(2 * self.config.border as u64)?;
+        Some(side.min(max as u64) as u32)
+    }
+
+    fn should_stop(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            self.aborted = true;
+        }
+        #[cfg(feature = "std")]
+        if self.nodes.is_multiple_of(1024) {
+            if let Some((start, limit)) = self.deadline {
+                if start.elapsed() >= limit {
+                    self.aborted = true;
+                }
+            }
+        }
+        self.aborted
+    }
+
+    fn record(&mut self) {
+        let mut used = Size::ZERO;
+        for rect in self.placed.iter().flatten() {
+            used.expand_with(rect);
+        }
+        let area = self.atlas_area(used.w, used.h);
+        if area < self.best_area {
+            self.best_area = area;
+            self.best.clone_from(&self.placed);
+        }
+    }
+
+    /// Widest part of `w` that can be filled by placing the rectangles left side by side.
+    fn fillable_width(&mut self, w: u32) -> u32 {
+        let left = self
+            .items
+            .iter()
+            .zip(&self.placed)
+            .filter(|(_, placed)| placed.is_none())
+            .map(|(item, _)| item);
+        sum_sides(&mut self.reachable, w, left, self.config.allow_flipping);
+        self.reachable
+            .iter()
+            .rposition(|&bits| bits != 0)
+            .map_or(0, |i| {
+                i as u32 * 64 + 63 - self.reachable[i].leading_zeros()
+            })
+    }
+
+    /// `used` is the area below the skyline, waste included.
+    fn search(&mut self, skyline: &[Segment], max_y: u32, used: u64) {
+        if self.should_stop() || self.best_area <= self.width_bound {
+            return;
+        }
+
+        if self.remaining == 0 {
+            self.record();
+            return;
+        }
+
+        let Some(max_h) = self.side_bound(self.width, self.config.bin_size().h) else {
+            return;
+        };
+        if max_y > max_h || used + self.remaining_area > self.width as u64 * max_h as u64 {
+            return;
+        }
+
+        // lowest gap, the leftmost one wins ties
+        let mut s = 0;
+        for (i, segment) in skyline.iter().enumerate() {
+            if segment.y < skyline[s].y {
+                s = i;
+            }
+        }
+        let gap = skyline[s];
+
+        // the rectangles left must still fit above the lowest gap, stacked when they can't be side by side
+        let left = self
+            .items
+            .iter()
+            .zip(&self.placed)
+            .filter(|(_, placed)| placed.is_none())
+            .map(|(item, _)| *item);
+        let flipping = self.config.allow_flipping;
+        if min_height(left, self.width, flipping).is_none_or(|h| gap.y as u64 + h > max_h as u64) {
+            return;
+        }
+
+        // lowest neighbour, where the gap gets raised when it's closed
+        let neighbours = [s.checked_sub(1), Some(s + 1)];
+        let neighbour_y = neighbours
+            .into_iter()
+            .flatten()
+            .filter_map(|n| skyline.get(n))
+            .map(|segment| segment.y)
+            .min();
+
+        // the part of the gap that no combination of the rectangles left can fill is wasted
+        if let Some(y) = neighbour_y {
+            let waste = (gap.w - self.fillable_width(gap.w)) as u64 * (y - gap.y) as u64;
+            if used + waste + self.remaining_area > self.width as u64 * max_h as u64 {
+                return;
+            }
+        }
+
+        let mut tried: Option<(u32, u32)> = None;
+        for i in 0..self.items.len() {
+            if self.placed[i].is_some() {
+                continue;
+            }
+            let item = self.items[i];
+            // identical rectangles, turned or not, give the same packings so only the first one left is tried
+            if tried == Some((item.w, item.h)) {
+                continue;
+            }
+            tried = Some((item.w, item.h));
+
+            for (w, h, flipped) in [(item.w, item.h, false), (item.h, item.w, true)] {
+                if flipped && (!self.config.allow_flipping || w == h) {
+                    continue;
+                }
+                if w > gap.w || gap.y + h > max_h {
+                    continue;
+                }
+
+                let rect = Rectf::from_rect(Rect::new(gap.x, gap.y, w, h), flipped != item.flipped);
+                let area = w as u64 * h as u64;
+                self.placed[i] = Some(rect);
+                self.remaining -= 1;
+                self.remaining_area -= area;
+
+                let skyline = place(skyline, s, w, gap.y + h);
+                self.search(&skyline, max_y.max(gap.y + h), used + area);
+
+                self.placed[i] = None;
+                self.remaining += 1;
+                self.remaining_area += area;
+                if self.aborted {
+                    return;
+                }
+            }
+        }
+
+        // close the gap, raising it to its lowest neighbour
+        let Some(y) = neighbour_y else {
+            return;
+        };
+        let skyline = place(skyline, s, gap.w, y);
+        self.search(&skyline, max_y, used + gap.w as u64 * (y - gap.y) as u64);
+    }
+}
+
+/// Sets in the bit set `reachable` every width up to `max` that can be made by placing some of the `items`
+/// side by side.
+fn sum_sides<'a>(
+    reachable: &mut Vec<u64>,
+    max: u32,
+    items: impl Iterator<Item = &'a Item>,
+    flipping: bool,
+) {
+    reachable.clear();
+    reachable.resize(max as usize / 64 + 1, 0);
+    reachable[0] = 1;
+    let sides = if flipping { 2 } else { 1 };
+    for item in items {
+        // each word gets the lower words shifted by the item sides, from the top so every item is used once
+        for i in (0..reachable.len()).rev() {
+            let mut bits = 0;
+            for side in [item.w, item.h].into_iter().take(sides) {
+                let (words, shift) = (side as usize / 64, side % 64);
+                let Some(from) = i.checked_sub(words) else {
+                    continue;
+                };
+                bits |= reachable[from] << shift;
+                if shift > 0 && from > 0 {
+                    bits |= reachable[from - 1] >> (64 - shift);
+                }
+            }
+            reachable[i] |= bits;
+        }
+    }
+    if let Some(last) = reachable.last_mut() {
+        *last &= u64::MAX >> (63 - max % 64);
+    }
+}
+
+/// Whether the bit set `reachable` of [`sum_sides`] has the width `w`.
+fn has_width(reachable: &[u64], w: u32) -> bool {
+    reachable
+        .get(w as usize / 64)
+        .is_some_and(|bits| bits >> (w % 64) & 1 == 1)
+}
+
+/// Lowest bin height that can hold the `items` in a bin `w` wide, `None` when one of them is wider.
+fn min_height(items: impl Iterator<Item = Item>, w: u32, flipping: bool) -> Option<u64> {
+    let sides = if flipping { 2 } else { 1 };
+    let mut tallest = 0;
+    // the rectangles wider than half of the bin can't be side by side, so they are stacked
+    let mut stacked = 0;
+    let mut area = 0;
+    for item in items {
+        let mut lowest: Option<u64> = None;
+        let mut narrow = false;
+        for (iw, ih) in [(item.w, item.h), (item.h, item.w)].into_iter().take(sides) {
+            if iw <= w {
+                lowest = Some(lowest.map_or(ih as u64, |h| h.min(ih as u64)));
+                narrow |= iw as u64 * 2 <= w as u64;
+            }
+        }
+        let h = lowest?;
+        tallest = tallest.max(h);
+        if !narrow {
+            stacked += h;
+        }
+        area += item.w as u64 * item.h as u64;
+    }
+    Some(tallest.max(stacked).max(area.div_ceil(w.max(1) as u64)))
+}
+
+/// Raises the first `w` of the segment `s` to `y`, merging the segments with the same height.
+fn place(skyline: &[Segment], s: usize, w: u32, y: u32) -> Vec<Segment> {
+    let gap = skyline[s];
+    let mut new = Vec::with_capacity(skyline.len() + 1);
+    new.extend_from_slice(&skyline[..s]);
+    new.push(Segment { x: gap.x, w, y });
+    if gap.w > w {
+        new.push(Segment {
+            x: gap.x + w,
+            w: gap.w - w,
+            y: gap.y,
+        });
+    }
+    new.extend_from_slice(&skyline[s + 1..]);
+
+    new.dedup_by(|b, a| {
+        if a.y == b.y {
+            a.w += b.w;
+            true
+        } else {
+            false
+        }
+    });
+    new
+}
+
+/// Finds the smallest atlas that can hold all the inputs, using an exact search made for small sets of
+/// rectangles (about 30), `config` defines the biggest atlas allowed.
+///
+/// The search uses bottom-left fill and tries the atlas widths that can be made by summing rectangle sides,
+/// from the widest rectangle up to the widest atlas that could beat the packing found by
+/// [`pack_with_best_size`]. When `limits` are reached the best packing found so far is returned, with
+/// [`OptimalResult::optimal`] set to `false`.
+pub fn pack_optimal<K: Copy>(
+    inputs: &[RectInput<K>],
+    config: PackerConfig,
+    limits: OptimalConfig,
+) -> OptimalResult<K> {
+    let bin = config.bin_size();
+
+    // the empty inputs and the ones that don't fit even the biggest atlas are left out
+    let mut items = vec![];
+    for (input, rect) in inputs.iter().enumerate() {
+        let Some((w, h)) = config.slot_size(rect.size.w, rect.size.h) else {
+            continue;
+        };
+        let fits = rect.size.area() > 0
+            && ((w <= bin.w && h <= bin.h) || (config.allow_flipping && h <= bin.w && w <= bin.h));
+        if !fits {
+            continue;
+        }
+        // lying flat, so the rectangles that only differ by their orientation are sorted together
+        if config.allow_flipping && h > w {
+            items.push(Item {
+                w: h,
+                h: w,
+                flipped: true,
+                input,
+            });
+        } else {
+            items.push(Item {
+                w,
+                h,
+                flipped: false,
+                input,
+            });
+        }
+    }
+    items.sort_by_key(|item| {
+        (
+            core::cmp::Reverse(item.w as u64 * item.h as u64),
+            item.w,
+            item.h,
+        )
+    });
+
+    let mut search = Search {
+        config: &config,
+        items,
+        placed: vec![],
+        remaining: 0,
+        remaining_area: 0,
+        width: 0,
+        width_bound: 0,
+        best: vec![],
+        best_area: u64::MAX,
+        nodes: 0,
+        max_nodes: limits.max_nodes,
+        #[cfg(feature = "std")]
+        deadline: limits
+            .time_limit
+            .map(|limit| (std::time::Instant::now(), limit)),
+        aborted: false,
+        reachable: vec![],
+    };
+    let n = search.items.len();
+    search.placed = vec![None; n];
+
+    // the heuristic packing is the first solution to beat
+    let fitting: Vec<RectInput<usize>> = search
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| RectInput {
+            size: inputs[item.input].size,
+            key: i,
+        })
+        .collect();
+    let max_size = Size::new(config.max_width, config.max_height);
+    let heuristic = pack_with_best_size(&fitting, MaxRectsPacker::new(config), max_size);
+    if heuristic.is_complete() {
+        search.best = vec![None; n];
+        for output in heuristic.rects {
+            let slot = config.slot_rect(&output.rect);
+            search.best[output.key] = Some(Rectf::from_rect(slot, output.rect.flipped));
+        }
+        search.best_area = heuristic.atlas_sizes.first().map_or(0, Size::area);
+    }
+
+    let flipping = config.allow_flipping;
+    let total_area: u64 = search.items.iter().map(|i| i.w as u64 * i.h as u64).sum();
+    // narrowest width and lowest height of each rectangle, the items lie flat when they can be flipped
+    let (min_w, min_h) = search
+        .items
+        .iter()
+        .map(|i| if flipping { (i.h, i.h) } else { (i.w, i.h) })
+        .fold((0, 0), |(w, h), (iw, ih)| (w.max(iw), h.max(ih)));
+
+    // a wider atlas can't beat the heuristic packing even with every rectangle at its lowest, and
+    // can't be filled by the rectangles side by side
+    let sides: u64 = search.items.iter().map(|i| i.w as u64).sum();
+    let max_w = search
+        .side_bound(min_h, bin.w)
+        .map_or(0, |w| w.min(sides.min(u32::MAX as u64) as u32));
+
+    // every width a bottom-left fill packing can end up with
+    let mut widths = vec![];
+    sum_sides(&mut widths, max_w, search.items.iter(), flipping);
+
+    // smallest atlas each width could give, so the promising widths are searched first
+    let mut candidates: Vec<(u64, u32)> = vec![];
+    for w in min_w.max(1)..=max_w {
+        if !has_width(&widths, w) {
+            continue;
+        }
+        let Some(h) = min_height(search.items.iter().copied(), w, flipping) else {
+            continue;
+        };
+        if h <= bin.h as u64 {
+            candidates.push((search.atlas_area(w, h as u32), w));
+        }
+    }
+    candidates.sort();
+
+    for (lower_bound, w) in candidates {
+        if n == 0 || lower_bound >= search.best_area {
+            break;
+        }
+
+        search.width = w;
+        search.width_bound = lower_bound;
+        search.remaining = n;
+        search.remaining_area = total_area;
+        search.search(&[Segment { x: 0, w, y: 0 }], 0, 0);
+        if search.aborted {
+            break;
+        }
+    }
+
+    // no packing found means the limits were reached before the first one
+    let found = !search.best.is_empty();
+
+    let mut placements = vec![None; inputs.len()];
+    for (item, slot) in search.items.iter().zip(&search.best) {
+        placements[item.input] = *slot;
+    }
+
+    let mut rects = vec![];
+    let mut unfit = vec![];
+    let mut used = Size::ZERO;
+    for (input, slot) in inputs.iter().zip(placements) {
+        match slot {
+            Some(slot) => {
+                used.expand_with(&slot);
+                rects.push(RectOutput {
+                    rect: config.content_rect(slot),
+                    atlas: 0,
+                    key: input.key,
+                });
+            }
+            None => unfit.push(input.key),
+        }
+    }
+
+    OptimalResult {
+        rects,
+        unfit,
+        size: config.atlas_size(used),
+        optimal: found && !search.aborted,
+    }
+}
@@ -1,149 +1,139 @@
-use crate::{Packer, Size};
+// binary search of the best bin size, ported from: rectpack2D https://github.com/TeamHypersomnia/rectpack2D
 
-// find best packing implementation
+use crate::{
+    pack_result, sort_order, PackResult, Packer, Packing, RectInput, Size, RECT_SORT_FUNCTIONS,
+};
+use alloc::{vec, vec::Vec};
+
+/// Stop the search once the step between two candidate bins gets this small.
+const DISCARD_STEP: u32 = 1;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-enum bin_dimension {
+enum BinDimension {
     Both,
     Width,
     Height,
 }
 
-enum PackingResult {
-    Area(u32),
+pub(crate) enum PackingResult {
+    /// Not every rectangle could be inserted, holds the total area that was inserted.
+    Area(u64),
+    /// Every rectangle was inserted in a bin of this size.
     Size(Size),
 }
 
+/// Try to insert every rectangle of `ordering` inside a bin of `bin` size,
+/// returns `Err` with the area inserted before the first failure.
+fn try_packing<P: Packer, K>(
+    packer: &mut P,
+    inputs: &[RectInput<K>],
+    ordering: &[usize],
+    bin: Size,
+) -> Result<(), u64> {
+    packer.reset(Some(bin));
+
+    let mut total_inserted_area = 0;
+    // empty inputs are never placed, they are reported as unfit
+    for input in ordering.iter().map(|&i| &inputs[i]) {
+        if input.size.area() == 0 {
+            continue;
+        }
+        if packer.insert(input.size.w, input.size.h).is_some() {
+            total_inserted_area += input.size.area();
+        } else {
+            return Err(total_inserted_area);
+        }
+    }
+
+    Ok(())
+}
+
 // This function will do a binary search on viable bin sizes,
 // starting from the biggest one: starting_bin.
 //
 // The search stops when the bin was successfully inserted into,
-// AND the bin size to be tried next differs in size from the last viable one by *less* then discard_step.
+// AND the bin size to be tried next differs in size from the last viable one by *less* then DISCARD_STEP.
 //
 // If we could not insert all input rectangles into a bin even as big as the starting_bin - the search fails.
-// In this case, we return the amount of space (total_area_type) inserted in total.
+// In this case, we return the amount of space inserted in total.
 //
 // If we've found a viable bin that is smaller or equal to starting_bin, the search succeeds.
-// In this case, we return the viable bin (rect_wh).
-
-#[inline(always)]
+// In this case, we return the viable bin.
 fn best_packing_for_ordering_impl<P: Packer, K>(
-    root: &mut P,
-    ordering: &[RectInput<K>],
+    packer: &mut P,
+    inputs: &[RectInput<K>],
+    ordering: &[usize],
     starting_bin: Size,
-    mut discard_step: i32,
-    tried_dimension: bin_dimension,
+    tried_dimension: BinDimension,
 ) -> PackingResult {
-    let mut candidate_bin = starting_bin;
-    let mut tries_before_discarding = 0;
-
-    if discard_step <= 0 {
-        tries_before_discarding = -discard_step;
-        discard_step = 1;
-    }
-
-    //std::cout << "best_packing_for_ordering_impl dim: " << int(tried_dimension) << " w: " << starting_bin.w << " h: " << starting_bin.h << std::endl;
-
-    let starting_step;
-    if tried_dimension == bin_dimension::Both {
-        candidate_bin.w /= 2;
-        candidate_bin.h /= 2;
+    let (try_w, try_h) = match tried_dimension {
+        BinDimension::Both => (true, true),
+        BinDimension::Width => (true, false),
+        BinDimension::Height => (false, true),
+    };
 
-        starting_step = candidate_bin.w / 2;
-    } else if tried_dimension == bin_dimension::Width {
+    let mut candidate_bin = starting_bin;
+    if try_w {
         candidate_bin.w /= 2;
-        starting_step = candidate_bin.w / 2;
-    } else {
+    }
+    if try_h {
         candidate_bin.h /= 2;
-        starting_step = candidate_bin.h / 2;
     }
 
-    let mut step = starting_step;
-    loop {
-        //std::cout << "candidate: " << candidate_bin.w << "x" << candidate_bin.h << std::endl;
-
-        root.reset(Some(candidate_bin));
+    // each dimension has its own step, so non square bins don't underflow
+    let mut step = Size::new(candidate_bin.w / 2, candidate_bin.h / 2);
 
-        let mut total_inserted_area = 0;
-
-        let mut all_inserted = true;
-        for rect in ordering {
-            if root.insert(rect.w, rect.h).is_some() {
-                total_inserted_area += rect.w * rect.h;
-            } else {
-                all_inserted = true;
-                break;
-            }
-        }
-
-        if all_inserted {
-            // attempt was successful. Try with a smaller bin.
-
-            if step as i32 <= discard_step {
-                if tries_before_discarding > 0 {
-                    tries_before_discarding -= 1;
-                } else {
+    loop {
+        match try_packing(packer, inputs, ordering, candidate_bin) {
+            Ok(()) => {
+                // attempt was successful, try with a smaller bin
+                if (!try_w || step.w <= DISCARD_STEP) && (!try_h || step.h <= DISCARD_STEP) {
                     return PackingResult::Size(candidate_bin);
                 }
-            }
 
-            if tried_dimension == bin_dimension::Both {
-                candidate_bin.w -= step;
-                candidate_bin.h -= step;
-            } else if tried_dimension == bin_dimension::Width {
-                candidate_bin.w -= step;
-            } else {
-                candidate_bin.h -= step;
+                if try_w {
+                    candidate_bin.w = candidate_bin.w.saturating_sub(step.w);
+                }
+                if try_h {
+                    candidate_bin.h = candidate_bin.h.saturating_sub(step.h);
+                }
             }
-
-            root.reset(Some(candidate_bin));
-        } else {
-            /* Attempt ended with failure. Try with a bigger bin. */
-
-            if tried_dimension == bin_dimension::Both {
-                candidate_bin.w += step;
-                candidate_bin.h += step;
-
-                if candidate_bin.area() > starting_bin.area() {
-                    return PackingResult::Area(total_inserted_area);
+            Err(total_inserted_area) => {
+                // attempt ended with failure, try with a bigger bin
+                if try_w {
+                    candidate_bin.w += step.w.max(1);
                 }
-            } else if tried_dimension == bin_dimension::Width {
-                candidate_bin.w += step;
-
-                if candidate_bin.w > starting_bin.w {
-                    return PackingResult::Area(total_inserted_area);
+                if try_h {
+                    candidate_bin.h += step.h.max(1);
                 }
-            } else {
-                candidate_bin.h += step;
 
-                if candidate_bin.h > starting_bin.h {
+                if candidate_bin.w > starting_bin.w || candidate_bin.h > starting_bin.h {
                     return PackingResult::Area(total_inserted_area);
                 }
             }
         }
 
-        step = 1.max(step / 2)
+        step.w = 1.max(step.w / 2);
+        step.h = 1.max(step.h / 2);
     }
 }
 
 fn best_packing_for_ordering<P: Packer, K>(
-    root: &mut P,
-    ordering: &[RectInput<K>],
-    starting_bin: &Size,
-    discard_step: i32,
+    packer: &mut P,
+    inputs: &[RectInput<K>],
+    ordering: &[usize],
+    starting_bin: Size,
 ) -> PackingResult {
-    let mut try_pack = |tried_dimension, starting_bin: Size| -> PackingResult {
-        best_packing_for_ordering_impl(root, ordering, starting_bin, discard_step, tried_dimension)
-    };
-
-    match (try_pack)(bin_dimension::Both, *starting_bin) {
+    match best_packing_for_ordering_impl(packer, inputs, ordering, starting_bin, BinDimension::Both)
+    {
         PackingResult::Size(mut best_bin) => {
-            if let PackingResult::Size(even_better) = (try_pack)(bin_dimension::Width, best_bin) {
-                best_bin = even_better;
-            }
-
-            if let PackingResult::Size(even_better) = (try_pack)(bin_dimension::Height, best_bin) {
-                best_bin = even_better;
+            // shrink each dimension individually
+            for dimension in [BinDimension::Width, BinDimension::Height] {
+                if let PackingResult::Size(even_better) =
+                    best_packing_for_ordering_impl(packer, inputs, ordering, best_bin, dimension)
+                {
+                    best_bin = even_better;
+                }
             }
             PackingResult::Size(best_bin)
         }
@@ -151,74 +141,119 @@ fn best_packing_for_ordering<P: Packer, K>(
     }
 }
 
-/*
-    This function will try to find the best bin size among the ones generated by all provided rectangle orders.
-    Only the best order will have results written to.
-
-    The function reports which of the rectangles did and did not fit in the end.
-*/
-
-fn find_best_packing_impl<'a, K: Copy + 'a>(
-    order_iterator: impl Iterator<Item = &'a [RectInput<K>]>,
-    input: PackerConfig,
-    discard_step: i32,
-    handle_successful_insertion: impl Fn(Frame<K>) -> bool,
-    handle_unsuccessful_insertion: impl Fn(&RectInput<K>) -> bool,
-) -> Size {
-    let max_bin = Size {
-        w: input.max_width,
-        h: input.max_height,
-    };
-
-    let mut best_order = None;
-    let mut best_total_inserted: i32 = -1;
-    let mut best_bin = max_bin;
-
-    /*
-        The root node is re-used on the TLS.
-        It is always reset before any packing attempt.
-    */
-
-    let mut root = EmptySpaces::new(0, 0);
-    root.enable_flipping = input.allow_flipping;
-
-    for order in order_iterator {
-        match best_packing_for_ordering(&mut root, order, &max_bin, discard_step) {
-            PackingResult::Area(total_inserted) => {
-                // Track which function inserts the most area in total,
-                // just in case that all orders will fail to fit into the largest allowed bin.
-                if best_order.is_none() {
-                    if total_inserted as i32 > best_total_inserted {
-                        best_order = Some(order);
-                        best_total_inserted = total_inserted as i32;
-                    }
-                }
-            }
-            PackingResult::Size(result_bin) => {
-                // Save the function if it performed the best.
-                if result_bin.w * result_bin.h <= best_bin.w * best_bin.h {
-                    best_order = Some(order);
-                    best_bin = result_bin;
-                }
-            }
+/// True when `result` beats the `best` result so far.
+pub(crate) fn is_better(best: &PackingResult, result: &PackingResult) -> bool {
+    match (best, result) {
+        // save the function if it performed the best
+        (PackingResult::Size(best_bin), PackingResult::Size(result_bin)) => {
+            result_bin.area() < best_bin.area()
+        }
+        (PackingResult::Area(_), PackingResult::Size(_)) => true,
+        (PackingResult::Size(_), PackingResult::Area(_)) => false,
+        // track which function inserts the most area in total,
+        // just in case that all orders will fail to fit into the largest allowed bin
+        (PackingResult::Area(best_total), PackingResult::Area(total_inserted)) => {
+            total_inserted > best_total
         }
     }
+}
+
+/// Searches the best bin for the inputs sorted by the sort function `order`.
+pub(crate) fn best_packing_for_sort_function<P: Packer, K>(
+    packer: &mut P,
+    inputs: &[RectInput<K>],
+    ordering: &mut Vec<usize>,
+    order: usize,
+    max_bin: Size,
+) -> PackingResult {
+    let cmp = |a: &RectInput<K>, b: &RectInput<K>| (RECT_SORT_FUNCTIONS[order])(a.size, b.size);
+    sort_order(inputs, ordering, Some(&cmp));
+    best_packing_for_ordering(packer, inputs, ordering, max_bin)
+}
 
-    let best_order = best_order.expect("no order found");
+/// Packs the inputs with the sort function `order` inside the bin found for it.
+pub(crate) fn pack_best<P: Packer, K: Copy>(
+    inputs: &[RectInput<K>],
+    mut packer: P,
+    (order, result): (usize, PackingResult),
+    max_bin: Size,
+) -> PackResult<K> {
+    let best_bin = match result {
+        PackingResult::Size(bin) => bin,
+        PackingResult::Area(_) => max_bin,
+    };
 
-    root.reset(&best_bin);
+    let cmp = |a: &RectInput<K>, b: &RectInput<K>| (RECT_SORT_FUNCTIONS[order])(a.size, b.size);
+    let mut ordering = vec![];
+    sort_order(inputs, &mut ordering, Some(&cmp));
 
-    for r in best_order {
-        if let Some(rect) = root.insert(r.w, r.h) {
-            if !(handle_successful_insertion)(rect) {
-                break;
-            }
+    let mut packing = Packing {
+        placements: vec![None; inputs.len()],
+        atlas_sizes: vec![],
+    };
+    packer.reset(Some(best_bin));
+    for &i in &ordering {
+        packing.placements[i] = packer
+            .insert(inputs[i].size.w, inputs[i].size.h)
+            .map(|rect| (rect, 0));
+    }
+    if packing.placements.iter().any(Option::is_some) {
+        packing.atlas_sizes.push(packer.used_area());
+    }
+
+    // the inputs left out that fit an empty atlas only ran out of room
+    let mut fits = vec![];
+    for (input, placement) in inputs.iter().zip(&packing.placements) {
+        if placement.is_none() {
+            packer.reset(Some(max_bin));
+            fits.push(packer.insert(input.size.w, input.size.h).is_some());
+        }
+    }
+
+    let mut result = pack_result(inputs, packing);
+    let left_out = core::mem::take(&mut result.unfit);
+    for (key, fits) in left_out.into_iter().zip(fits) {
+        if fits {
+            result.overflow.push(key);
         } else {
-            if !(handle_unsuccessful_insertion)(r) {
-                break;
-            }
+            result.unfit.push(key);
+        }
+    }
+    result
+}
+
+/// Sorts the input data using the heuristics defined in [`RECT_SORT_FUNCTIONS`] and binary searches the smallest
+/// atlas (first in both dimensions, then only the width and lastly only the height) that can hold all of them,
+/// `max_size` is the biggest atlas allowed, padding, border and flipping are the ones of the `packer`.
+///
+/// Every rectangle ends up in a single atlas, the output keeps the order of the inputs. If not every input fits
+/// inside the biggest atlas the ordering that inserted the most area is used, the inputs that don't fit even
+/// inside an empty atlas are reported in [`PackResult::unfit`] and the ones left out for lack of room in
+/// [`PackResult::overflow`].
+pub fn pack_with_best_size<P: Packer, K: Copy>(
+    inputs: &[RectInput<K>],
+    mut packer: P,
+    max_size: Size,
+) -> PackResult<K> {
+    // the inputs are sorted through their indices
+    let mut ordering = vec![];
+
+    // index of the best sort function and its result
+    let mut best: Option<(usize, PackingResult)> = None;
+
+    for order in 0..RECT_SORT_FUNCTIONS.len() {
+        let result =
+            best_packing_for_sort_function(&mut packer, inputs, &mut ordering, order, max_size);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best)| is_better(best, &result))
+        {
+            best = Some((order, result));
         }
     }
 
-    return root.get_rects_aabb();
+    match best {
+        Some(best) => pack_best(inputs, packer, best, max_size),
+        None => PackResult::default(),
+    }
 }
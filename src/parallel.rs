@@ -0,0 +1,101 @@
+use crate::optimize::{best_packing_for_sort_function, is_better, pack_best, PackingResult};
+use crate::{
+    pack_order, pack_result, sort_order, PackResult, Packer, Packing, RectInput, Size,
+    RECT_SORT_FUNCTIONS,
+};
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+use rayon::prelude::*;
+
+/// Comparator used by [`par_pack_with`], same as [`RectOrdering`](crate::RectOrdering) but it can be
+/// shared between threads.
+pub type ParRectOrdering<K> = dyn Fn(&RectInput<K>, &RectInput<K>) -> Ordering + Sync;
+
+/// Same as [`pack`](crate::pack) but each ordering is packed in parallel by its own clone of the `packer`.
+///
+/// Gives the same result of [`pack`](crate::pack).
+pub fn par_pack<P, K>(inputs: &[RectInput<K>], packer: P) -> PackResult<K>
+where
+    P: Packer + Clone + Send + Sync,
+    K: Copy + Sync,
+{
+    let orderings = RECT_SORT_FUNCTIONS
+        .map(|cmp| move |a: &RectInput<K>, b: &RectInput<K>| (cmp)(a.size, b.size));
+    let orderings = orderings.each_ref().map(|cmp| cmp as &ParRectOrdering<K>);
+    par_pack_with(inputs, packer, &orderings)
+}
+
+/// Same as [`pack_with`](crate::pack_with) but each ordering is packed in parallel by its own clone of
+/// the `packer`.
+///
+/// Gives the same result of [`pack_with`](crate::pack_with), when two orderings use the same area the
+/// first one wins.
+pub fn par_pack_with<P, K>(
+    inputs: &[RectInput<K>],
+    packer: P,
+    orderings: &[&ParRectOrdering<K>],
+) -> PackResult<K>
+where
+    P: Packer + Clone + Send + Sync,
+    K: Copy + Sync,
+{
+    let best = (0..orderings.len().max(1))
+        .into_par_iter()
+        .map_init(
+            || packer.clone(),
+            |packer, i| {
+                let mut order = vec![];
+                sort_order(inputs, &mut order, orderings.get(i).copied());
+
+                let mut packing = Packing::default();
+                let area = pack_order(inputs, &order, packer, &mut packing);
+                (area, i, packing)
+            },
+        )
+        .min_by_key(|&(area, i, _)| (area, i));
+
+    match best {
+        Some((_, _, packing)) => pack_result(inputs, packing),
+        None => PackResult::default(),
+    }
+}
+
+/// Same as [`pack_with_best_size`](crate::pack_with_best_size) but the bin sizes of each sort function
+/// are searched in parallel by its own clone of the `packer`.
+///
+/// Gives the same result of [`pack_with_best_size`](crate::pack_with_best_size).
+pub fn par_pack_with_best_size<P, K>(
+    inputs: &[RectInput<K>],
+    packer: P,
+    max_size: Size,
+) -> PackResult<K>
+where
+    P: Packer + Clone + Send + Sync,
+    K: Copy + Sync,
+{
+    let results: Vec<PackingResult> = (0..RECT_SORT_FUNCTIONS.len())
+        .into_par_iter()
+        .map_init(
+            || (packer.clone(), vec![]),
+            |(packer, ordering), order| {
+                best_packing_for_sort_function(packer, inputs, ordering, order, max_size)
+            },
+        )
+        .collect();
+
+    // picked in order, so ties are broken like the sequential search
+    let mut best: Option<(usize, PackingResult)> = None;
+    for (order, result) in results.into_iter().enumerate() {
+        if best
+            .as_ref()
+            .is_none_or(|(_, best)| is_better(best, &result))
+        {
+            best = Some((order, result));
+        }
+    }
+
+    match best {
+        Some(best) => pack_best(inputs, packer, best, max_size),
+        None => PackResult::default(),
+    }
+}
@@ -0,0 +1,218 @@
+// based on: RectangleBinPack https://github.com/juj/RectangleBinPack
+// paper: "A Thousand Ways to Pack the Bin" by Jukka Jylänki
+
+use crate::{Packer, PackerConfig, Rect, Rectf, Size};
+use alloc::{vec, vec::Vec};
+
+/// Rule used by [`ShelfPacker`] to choose the shelf that receives each rectangle.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ShelfChoice {
+    /// Only the last shelf is used, works like the [`StripPacker`](crate::StripPacker).
+    NextFit,
+    /// First shelf the rectangle fits.
+    #[default]
+    FirstFit,
+    /// Shelf that leaves the smallest width left.
+    BestWidthFit,
+    /// Shelf with the smallest height left above the rectangle.
+    BestHeightFit,
+    /// Shelf with the smallest area left.
+    BestAreaFit,
+    /// Shelf that leaves the biggest width left.
+    WorstWidthFit,
+}
+
+#[derive(Clone)]
+struct Shelf {
+    y: u32,
+    h: u32,
+    /// Width already used by the rectangles, they are placed from left to right.
+    used_w: u32,
+}
+
+/// Places the rectangles side by side in horizontal shelves, every shelf is kept open
+/// until it runs out of space.
+///
+/// *Works best when all the rectangles have about the same height*, like icons or glyphs
+#[derive(Clone)]
+pub struct ShelfPacker {
+    config: PackerConfig,
+    choice: ShelfChoice,
+    shelves: Vec<Shelf>,
+    used_area: Size,
+}
+
+impl ShelfPacker {
+    pub fn new(config: PackerConfig) -> Self {
+        Self {
+            config,
+            choice: ShelfChoice::default(),
+            shelves: vec![],
+            used_area: Size::ZERO,
+        }
+    }
+
+    /// Changes the rule used to choose the shelf of each rectangle.
+    pub fn with_choice(mut self, choice: ShelfChoice) -> Self {
+        self.choice = choice;
+        self
+    }
+
+    pub const fn choice(&self) -> ShelfChoice {
+        self.choice
+    }
+
+    /// Height that the shelf `i` can hold, the last shelf is still open so it can grow up to the atlas height.
+    fn max_height(&self, i: usize, bin: Size) -> u32 {
+        let shelf = &self.shelves[i];
+        if i + 1 == self.shelves.len() {
+            bin.h - shelf.y
+        } else {
+            shelf.h
+        }
+    }
+
+    /// Orientation of a rectangle of (w, h) that fits the shelf `i`, returns the width and height and
+    /// if it was flipped.
+    fn fit_shelf(&self, i: usize, bin: Size, w: u32, h: u32) -> Option<(u32, u32, bool)> {
+        let shelf = &self.shelves[i];
+        let free_w = bin.w - shelf.used_w;
+        let max_h = self.max_height(i, bin);
+
+        let mut best: Option<(u32, u32, bool)> = None;
+        for (w, h, flipped) in [(w, h, false), (h, w, true)] {
+            if flipped && (!self.config.allow_flipping || w == h) {
+                continue;
+            }
+            if w > free_w || h > max_h {
+                continue;
+            }
+
+            // prefer standing inside the shelf height, it uses less of the shelf width,
+            // otherwise lie flat so the shelf grows less
+            let better = match best {
+                None => true,
+                Some((best_w, best_h, _)) => {
+                    let fits = h <= shelf.h;
+                    let best_fits = best_h <= shelf.h;
+                    if fits != best_fits {
+                        fits
+                    } else if fits {
+                        w < best_w
+                    } else {
+                        h < best_h
+                    }
+                }
+            };
+            if better {
+                best = Some((w, h, flipped));
+            }
+        }
+
+        best
+    }
+
+    /// How good is to place a rectangle of (w, h) in the shelf `i`, lower scores are better.
+    fn score(&self, i: usize, bin: Size, w: u32, h: u32) -> i64 {
+        let shelf = &self.shelves[i];
+        let free_w = (bin.w - shelf.used_w - w) as i64;
+        let free_h = shelf.h.saturating_sub(h) as i64;
+
+        match self.choice {
+            ShelfChoice::NextFit | ShelfChoice::FirstFit => 0,
+            ShelfChoice::BestWidthFit => free_w,
+            ShelfChoice::BestHeightFit => free_h,
+            ShelfChoice::BestAreaFit => {
+                (bin.w - shelf.used_w) as i64 * shelf.h.max(h) as i64 - w as i64 * h as i64
+            }
+            ShelfChoice::WorstWidthFit => -free_w,
+        }
+    }
+
+    fn find_shelf(&self, bin: Size, w: u32, h: u32) -> Option<(usize, u32, u32, bool)> {
+        let start = match self.choice {
+            ShelfChoice::NextFit => self.shelves.len().saturating_sub(1),
+            _ => 0,
+        };
+
+        let mut best_score = i64::MAX;
+        let mut best = None;
+        for i in start..self.shelves.len() {
+            if let Some((w, h, flipped)) = self.fit_shelf(i, bin, w, h) {
+                let score = self.score(i, bin, w, h);
+                if score < best_score {
+                    best_score = score;
+                    best = Some((i, w, h, flipped));
+                }
+                if self.choice == ShelfChoice::FirstFit {
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Opens a new shelf for a rectangle of (w, h), returns its index and the rectangle orientation.
+    fn open_shelf(&mut self, bin: Size, w: u32, h: u32) -> Option<(usize, u32, u32, bool)> {
+        let y = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.h);
+
+        // lie flat, so the shelf is as short as possible
+        let (w, h, flipped) = if self.config.allow_flipping && h > w && h <= bin.w {
+            (h, w, true)
+        } else {
+            (w, h, false)
+        };
+
+        if w > bin.w || h > bin.h - y {
+            return None;
+        }
+
+        self.shelves.push(Shelf { y, h, used_w: 0 });
+        Some((self.shelves.len() - 1, w, h, flipped))
+    }
+}
+
+impl Packer for ShelfPacker {
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
+        let bin = self.config.bin_size();
+
+        let (i, w, h, flipped) = match self.find_shelf(bin, w, h) {
+            Some(found) => found,
+            None => self.open_shelf(bin, w, h)?,
+        };
+
+        let shelf = &mut self.shelves[i];
+        // only the last shelf can grow
+        shelf.h = shelf.h.max(h);
+
+        let rect = Rectf::from_rect(Rect::new(shelf.used_w, shelf.y, w, h), flipped);
+        shelf.used_w += w;
+
+        self.used_area.expand_with(&rect);
+
+        Some(self.config.content_rect(rect))
+    }
+
+    fn reset(&mut self, resize: Option<Size>) {
+        if let Some(Size { w, h }) = resize {
+            self.config.max_width = w;
+            self.config.max_height = h;
+        }
+        self.used_area = Size::ZERO;
+        self.shelves.clear();
+    }
+
+    fn grow(&mut self, size: Size) {
+        // every shelf gets wider and the last one can get taller
+        self.config.grow(size);
+    }
+
+    fn used_area(&self) -> Size {
+        self.config.atlas_size(self.used_area)
+    }
+}
@@ -1,8 +1,8 @@
 // original source copied from: texture_packer https://github.com/PistonDevelopers/texture_packer
 
-use crate::Size;
+use crate::{FreeRectChoice, GuillotinePacker, Size, SplitRule};
 
-use super::{Packer, PackerConfig, Rect, Rectf};
+use super::{DynamicPacker, Packer, PackerConfig, Rect, Rectf};
 use alloc::{vec, vec::Vec};
 use core::cmp::max;
 
@@ -25,11 +25,25 @@ impl Skyline {
     }
 }
 
+/// Rule used by [`SkylinePacker`] to choose where each rectangle goes.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum SkylineHeuristic {
+    /// Keeps the rectangle bottom as close to the top as possible, ties are broken by the skyline width.
+    #[default]
+    BottomLeft,
+    /// Chooses the position that leaves the least wasted area below the rectangle, ties are broken by
+    /// the rectangle bottom.
+    MinWaste,
+}
+
 #[derive(Clone)]
 pub struct SkylinePacker {
     config: PackerConfig,
+    heuristic: SkylineHeuristic,
     // the skylines are sorted by their `x` position
     skylines: Vec<Skyline>,
+    /// Keeps the holes left under the skyline, so they can be used later.
+    waste: Option<GuillotinePacker>,
     used_area: Size,
 }
 
@@ -38,26 +52,52 @@ impl SkylinePacker {
         let skylines = vec![Skyline {
             x: 0,
             y: 0,
-            w: config.max_width,
+            w: config.bin_size().w,
         }];
 
         SkylinePacker {
             config,
+            heuristic: SkylineHeuristic::default(),
             skylines,
+            waste: None,
             used_area: Size::ZERO,
         }
     }
 
+    /// Changes the heuristic used to place the rectangles.
+    pub fn with_heuristic(mut self, heuristic: SkylineHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    pub const fn heuristic(&self) -> SkylineHeuristic {
+        self.heuristic
+    }
+
+    /// When enabled, the space trapped under each rectangle is kept in a waste map that is tried before
+    /// the skyline. Default value is `false`.
+    ///
+    /// Based on "A Thousand Ways to Pack the Bin" by Jukka Jylänki, it packs almost as good as the
+    /// [`MaxRectsPacker`](crate::MaxRectsPacker) but it's much faster.
+    pub fn with_waste_map(mut self, enabled: bool) -> Self {
+        self.waste = enabled.then(|| {
+            GuillotinePacker::empty(self.config)
+                .with_choice(FreeRectChoice::BestShortSideFit)
+                .with_split(SplitRule::MaximizeArea)
+                .with_merge(true)
+        });
+        self
+    }
+
     // return `rect` if rectangle (w, h) can fit the skyline started at `i`
     fn can_put(&self, mut i: usize, w: u32, h: u32) -> Option<Rect> {
+        let bin = self.config.bin_size();
         let mut rect = Rect::new(self.skylines[i].x, 0, w, h);
         let mut width_left = rect.w;
         loop {
             rect.y = max(rect.y, self.skylines[i].y);
-            // the source rect is too large
-            if (rect.x + rect.w) > self.config.max_width
-                || (rect.y + rect.h) > self.config.max_height
-            {
+            // the source rect is too large, the skyline is inside the bin so this can't overflow
+            if rect.w > bin.w - rect.x || rect.h > bin.h - rect.y {
                 return None;
             }
             if self.skylines[i].w >= width_left {
@@ -69,36 +109,72 @@ impl SkylinePacker {
         }
     }
 
+    /// Area between the skyline and the bottom of `rect`, that will be lost if `rect` is placed.
+    fn wasted_area(&self, rect: &Rect) -> u64 {
+        self.skylines
+            .iter()
+            .map(|skyline| {
+                let start = skyline.x.max(rect.x);
+                let end = (skyline.x + skyline.w).min(rect.x + rect.w);
+                if start < end && skyline.y < rect.y {
+                    (end - start) as u64 * (rect.y - skyline.y) as u64
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// How good is to place `rect` over the skyline `i`, lower scores are better.
+    fn score(&self, i: usize, rect: &Rect) -> (u64, u64) {
+        let bottom = (rect.y + rect.h) as u64;
+        match self.heuristic {
+            // keep the `bottom` and `width` as small as possible
+            SkylineHeuristic::BottomLeft => (bottom, self.skylines[i].w as u64),
+            SkylineHeuristic::MinWaste => (self.wasted_area(rect), bottom),
+        }
+    }
+
     fn find_skyline(&self, w: u32, h: u32) -> Option<(usize, Rect)> {
-        let mut bottom = core::u32::MAX;
-        let mut width = core::u32::MAX;
-        let mut index = None;
-        let mut rect = Rect::new(0, 0, 0, 0);
+        let mut best_score = (u64::MAX, u64::MAX);
+        let mut best = None;
 
-        // keep the `bottom` and `width` as small as possible
         for i in 0..self.skylines.len() {
             if let Some(r) = self.can_put(i, w, h) {
-                if r.bottom() < bottom || (r.bottom() == bottom && self.skylines[i].w < width) {
-                    bottom = r.bottom();
-                    width = self.skylines[i].w;
-                    index = Some(i);
-                    rect = r;
+                let score = self.score(i, &r);
+                if score < best_score {
+                    best_score = score;
+                    best = Some((i, r));
                 }
             }
 
             if self.config.allow_flipping {
                 if let Some(r) = self.can_put(i, h, w) {
-                    if r.bottom() < bottom || (r.bottom() == bottom && self.skylines[i].w < width) {
-                        bottom = r.bottom();
-                        width = self.skylines[i].w;
-                        index = Some(i);
-                        rect = r;
+                    let score = self.score(i, &r);
+                    if score < best_score {
+                        best_score = score;
+                        best = Some((i, r));
                     }
                 }
             }
         }
 
-        index.map(|x| (x, rect))
+        best
+    }
+
+    /// Gives the holes between the skyline and the bottom of `rect` to the waste map.
+    fn collect_waste(&mut self, rect: &Rect) {
+        let Some(waste) = &mut self.waste else {
+            return;
+        };
+
+        for skyline in &self.skylines {
+            let start = skyline.x.max(rect.x);
+            let end = (skyline.x + skyline.w).min(rect.x + rect.w);
+            if start < end && skyline.y < rect.y {
+                waste.add_free(Rect::new(start, skyline.y, end - start, rect.y - skyline.y));
+            }
+        }
     }
 
     fn split(&mut self, index: usize, rect: &Rect) {
@@ -108,8 +184,9 @@ impl SkylinePacker {
             w: rect.w,
         };
 
-        assert!(skyline.right() <= self.config.max_width);
-        assert!(skyline.y <= self.config.max_height);
+        let bin = self.config.bin_size();
+        assert!(skyline.right() <= bin.w);
+        assert!(skyline.y <= bin.h);
 
         self.skylines.insert(index, skyline);
 
@@ -132,6 +209,23 @@ impl SkylinePacker {
         }
     }
 
+    /// Splits the skyline that goes over `x` in two, so a skyline starts at `x`.
+    fn cut(&mut self, x: u32) {
+        if let Some(i) = self
+            .skylines
+            .iter()
+            .position(|s| s.left() < x && x <= s.right())
+        {
+            let right = Skyline {
+                x,
+                y: self.skylines[i].y,
+                w: self.skylines[i].right() + 1 - x,
+            };
+            self.skylines[i].w -= right.w;
+            self.skylines.insert(i + 1, right);
+        }
+    }
+
     fn merge(&mut self) {
         let mut i = 1;
         while i < self.skylines.len() {
@@ -145,13 +239,58 @@ impl SkylinePacker {
     }
 }
 
+impl DynamicPacker for SkylinePacker {
+    /// The space is only given back when nothing was placed on top of the rectangle,
+    /// otherwise it stays lost like any other hole under the skyline.
+    fn remove(&mut self, rect: &Rectf) {
+        let slot = self.config.slot_rect(rect);
+        let (start, end) = (slot.x, slot.x + slot.w);
+        let top = slot.y + slot.h;
+
+        if !self
+            .skylines
+            .iter()
+            .filter(|s| s.x < end && s.x + s.w > start)
+            .all(|s| s.y == top)
+        {
+            if let Some(waste) = &mut self.waste {
+                waste.add_free(slot);
+            }
+            return;
+        }
+
+        self.cut(start);
+        self.cut(end);
+        for skyline in &mut self.skylines {
+            if skyline.x >= start && skyline.x + skyline.w <= end {
+                skyline.y = slot.y;
+            }
+        }
+        self.merge();
+    }
+}
+
 impl Packer for SkylinePacker {
     fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
+
+        if let Some(rect) = self.waste.as_mut().and_then(|waste| waste.place(w, h)) {
+            self.used_area.expand_with(&rect);
+            return Some(self.config.content_rect(rect));
+        }
+
         if let Some((i, rect)) = self.find_skyline(w, h) {
+            self.collect_waste(&rect);
             self.split(i, &rect);
             self.merge();
             self.used_area.expand_with(&rect);
-            Some(Rectf::from_rect(rect, w != rect.w))
+            Some(
+                self.config
+                    .content_rect(Rectf::from_rect(rect, w != rect.w)),
+            )
         } else {
             None
         }
@@ -163,15 +302,31 @@ impl Packer for SkylinePacker {
             self.config.max_height = h;
         }
         self.used_area = Size::ZERO;
+        if let Some(waste) = &mut self.waste {
+            waste.clear();
+        }
         self.skylines.clear();
         self.skylines.push(Skyline {
             x: 0,
             y: 0,
-            w: self.config.max_width,
+            w: self.config.bin_size().w,
         });
     }
 
+    fn grow(&mut self, size: Size) {
+        let (old, new) = self.config.grow(size);
+        if new.w > old.w {
+            // the new space on the right is empty all the way to the top
+            self.skylines.push(Skyline {
+                x: old.w,
+                y: 0,
+                w: new.w - old.w,
+            });
+            self.merge();
+        }
+    }
+
     fn used_area(&self) -> Size {
-        self.used_area
+        self.config.atlas_size(self.used_area)
     }
 }
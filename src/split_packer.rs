@@ -1,4 +1,4 @@
-use crate::{Packer, PackerConfig, Rect, Rectf, Size};
+use crate::{merge_rects, DynamicPacker, Packer, PackerConfig, Rect, Rectf, Size};
 use alloc::{vec, vec::Vec};
 
 struct Splits {
@@ -7,15 +7,10 @@ struct Splits {
 }
 
 impl From<Rect> for Splits {
-    #[allow(invalid_value)]
     fn from(space: Rect) -> Self {
         Self {
             count: 1,
-            spaces: [
-                space,
-                // safety: `Rect` is just made out of unsigned integers and the second one doesnt need to be initialized
-                unsafe { core::mem::MaybeUninit::uninit().assume_init() },
-            ],
+            spaces: [space, Rect::new(0, 0, 0, 0)],
         }
     }
 }
@@ -27,21 +22,21 @@ impl From<[Rect; 2]> for Splits {
 }
 
 impl Splits {
-    #[allow(invalid_value)]
+    const EMPTY: [Rect; 2] = [Rect::new(0, 0, 0, 0), Rect::new(0, 0, 0, 0)];
+
     const fn failed() -> Self {
         Self {
-            count: core::u32::MAX,
-            // safety: spaces are invalid
-            spaces: unsafe { core::mem::MaybeUninit::uninit().assume_init() },
+            count: u32::MAX,
+            // spaces are invalid
+            spaces: Self::EMPTY,
         }
     }
 
-    #[allow(invalid_value)]
     const fn none() -> Self {
         Self {
             count: 0,
-            // safety: there is no spaces in this split
-            spaces: unsafe { core::mem::MaybeUninit::uninit().assume_init() },
+            // there is no spaces in this split
+            spaces: Self::EMPTY,
         }
     }
 
@@ -77,14 +72,14 @@ fn insert_and_split(w: u32, h: u32, space_available: Rect) -> Splits {
     // (e.g. image = 20x40, candidate space = 30x40)
     // we delete the space and create a single split. In this case a 10x40 space.
     if free_w > 0 && free_h == 0 {
-        let mut r = space_available.clone();
+        let mut r = space_available;
         r.x += w;
         r.w -= w;
         return r.into();
     }
 
     if free_w == 0 && free_h > 0 {
-        let mut r = space_available.clone();
+        let mut r = space_available;
         r.y += h;
         r.h -= h;
         return r.into();
@@ -117,7 +112,7 @@ fn insert_and_split(w: u32, h: u32, space_available: Rect) -> Splits {
         let lesser_split = Rect {
             x: space_available.x,
             y: space_available.y + h,
-            w: w,
+            w,
             h: free_h,
         };
 
@@ -135,10 +130,10 @@ fn insert_and_split(w: u32, h: u32, space_available: Rect) -> Splits {
         x: space_available.x + w,
         y: space_available.y,
         w: free_w,
-        h: h,
+        h,
     };
 
-    return [bigger_split, lesser_split].into();
+    [bigger_split, lesser_split].into()
 }
 
 /// [`Rect`] that could be flipped sideway (rotated by 90 degrees clockwise)
@@ -176,12 +171,13 @@ impl SplitPacker {
             spaces: vec![],
             config,
         };
+        let bin = config.bin_size();
         tmp.spaces.push(
             Rect {
                 x: 0,
                 y: 0,
-                w: config.max_width,
-                h: config.max_width,
+                w: bin.w,
+                h: bin.h,
             }
             .into(),
         );
@@ -204,7 +200,7 @@ impl SplitPacker {
         }
 
         // rectangles sorted globably performs much better
-        self.spaces.sort_by(|a, b| a.area.cmp(&b.area));
+        self.spaces.sort_by_key(|a| a.area);
 
         if flipped {
             core::mem::swap(&mut rect.w, &mut rect.h);
@@ -214,12 +210,32 @@ impl SplitPacker {
 
         self.used_area.expand_with(&rect);
 
-        Some(rect)
+        Some(self.config.content_rect(rect))
+    }
+
+    /// Merges every pair of spaces that share a whole edge, then sorts them by area.
+    fn merge_spaces(&mut self) {
+        let mut rects = self.spaces.iter().map(|space| space.rect).collect();
+        merge_rects(&mut rects);
+        self.spaces = rects.into_iter().map(Recta::from).collect();
+        self.spaces.sort_by_key(|a| a.area);
+    }
+}
+
+impl DynamicPacker for SplitPacker {
+    fn remove(&mut self, rect: &Rectf) {
+        let space = self.config.slot_rect(rect);
+        self.spaces.push(space.into());
+        self.merge_spaces();
     }
 }
 
 impl Packer for SplitPacker {
     fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
+        let (w, h) = self.config.slot_size(w, h)?;
         for i in 0..self.spaces.len() {
             let candidate_space = self.spaces[i];
 
@@ -288,19 +304,33 @@ impl Packer for SplitPacker {
         }
 
         self.used_area = Size::ZERO;
+        let bin = self.config.bin_size();
         self.spaces.clear();
         self.spaces.push(
             Rect {
                 x: 0,
                 y: 0,
-                w: self.config.max_width,
-                h: self.config.max_height,
+                w: bin.w,
+                h: bin.h,
             }
             .into(),
         );
     }
 
+    fn grow(&mut self, size: Size) {
+        let (old, new) = self.config.grow(size);
+        if new.w > old.w {
+            self.spaces
+                .push(Rect::new(old.w, 0, new.w - old.w, new.h).into());
+        }
+        if new.h > old.h {
+            self.spaces
+                .push(Rect::new(0, old.h, old.w, new.h - old.h).into());
+        }
+        self.merge_spaces();
+    }
+
     fn used_area(&self) -> Size {
-        self.used_area
+        self.config.atlas_size(self.used_area)
     }
 }
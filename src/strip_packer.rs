@@ -36,38 +36,50 @@ impl StripPacker {
         if self.overflowed {
             1.0
         } else {
-            (self.cursor[1] + self.row_height) as f32 / self.config.max_height as f32
+            (self.cursor[1] + self.row_height) as f32 / self.config.bin_size().h as f32
         }
     }
 }
 
 impl Packer for StripPacker {
     fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+        if w == 0 || h == 0 {
+            return None;
+        }
         // this current algorithm works best for fonts
         // because they all use the have about the same height
 
         // todo: keep previous rows available until there's some space left
         // todo: hability to rotate images and better fit other images
 
-        if w > self.config.max_width {
+        let (w, h) = self.config.slot_size(w, h)?;
+        let bin = self.config.bin_size();
+
+        if w > bin.w {
             return None;
         }
 
-        if self.cursor[0] + w > self.config.max_width {
+        let mut cursor = self.cursor;
+        let mut row_height = self.row_height;
+
+        if w > bin.w - cursor[0] {
             // new row:
-            self.cursor[0] = 0;
-            self.cursor[1] += self.row_height;
-            self.row_height = 0;
+            cursor[0] = 0;
+            cursor[1] += row_height;
+            row_height = 0;
         }
 
-        self.row_height = self.row_height.max(h);
-        let required_height = self.cursor[1] + self.row_height;
+        row_height = row_height.max(h);
 
-        if required_height > self.config.max_height {
+        if row_height > bin.h - cursor[1] {
             self.overflowed = true;
             return None;
         }
 
+        // only commit the new row once the rectangle is known to fit
+        self.cursor = cursor;
+        self.row_height = row_height;
+
         let rect = Rectf {
             x: self.cursor[0],
             y: self.cursor[1],
@@ -80,7 +92,7 @@ impl Packer for StripPacker {
 
         self.used_area.expand_with(&rect);
 
-        Some(rect)
+        Some(self.config.content_rect(rect))
     }
 
     fn reset(&mut self, resize: Option<Size>) {
@@ -94,7 +106,13 @@ impl Packer for StripPacker {
         self.used_area = Size::ZERO;
     }
 
+    fn grow(&mut self, size: Size) {
+        // the rows are kept, and the current one can now go further
+        self.config.grow(size);
+        self.overflowed = false;
+    }
+
     fn used_area(&self) -> Size {
-        self.used_area
+        self.config.atlas_size(self.used_area)
     }
 }
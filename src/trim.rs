@@ -0,0 +1,96 @@
+#[cfg(feature = "image")]
+use crate::RectInput;
+use crate::{Rect, Size};
+#[cfg(feature = "image")]
+use alloc::vec::Vec;
+#[cfg(feature = "image")]
+use image::{GenericImageView, Rgba};
+
+/// Configuration of [`trim`].
+#[cfg(feature = "image")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TrimConfig {
+    /// Pixels with an alpha lower or equal to this are considered transparent. Default value is `0`.
+    pub alpha_threshold: u8,
+}
+
+/// Part of a source image kept by `trim` (with the `image` feature), keep it alongside the
+/// [`RectOutput`](crate::RectOutput) of the image to draw it at the right place of the untrimmed frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Trim {
+    /// Area of the source image that is packed, its position is the offset of the packed pixels
+    /// inside the untrimmed frame.
+    pub rect: Rect,
+    /// Size of the untrimmed source image.
+    pub source_size: Size,
+}
+
+impl Trim {
+    /// Trim that keeps the whole source image.
+    pub const fn none(source_size: Size) -> Self {
+        Self {
+            rect: Rect::new(0, 0, source_size.w, source_size.h),
+            source_size,
+        }
+    }
+
+    /// True when some of the source image was trimmed away.
+    pub fn is_trimmed(&self) -> bool {
+        self.rect.size() != self.source_size
+    }
+}
+
+/// Finds the bounding box of the pixels of `image` that aren't transparent, its size is the one to pack.
+///
+/// Images without any visible pixel keep their top-left pixel, so they still get a rectangle.
+#[cfg(feature = "image")]
+pub fn trim<I: GenericImageView<Pixel = Rgba<u8>>>(image: &I, config: TrimConfig) -> Trim {
+    let (w, h) = image.dimensions();
+    let visible = |x: u32, y: u32| image.get_pixel(x, y).0[3] > config.alpha_threshold;
+
+    let Some(top) = (0..h).find(|&y| (0..w).any(|x| visible(x, y))) else {
+        return Trim {
+            rect: Rect::new(0, 0, w.min(1), h.min(1)),
+            source_size: Size::new(w, h),
+        };
+    };
+    // a visible row exists, so each of these searches finds a line
+    let bottom = (top..h)
+        .rev()
+        .find(|&y| (0..w).any(|x| visible(x, y)))
+        .unwrap();
+    let left = (0..w)
+        .find(|&x| (top..=bottom).any(|y| visible(x, y)))
+        .unwrap();
+    let right = (left..w)
+        .rev()
+        .find(|&x| (top..=bottom).any(|y| visible(x, y)))
+        .unwrap();
+
+    Trim {
+        rect: Rect::new(left, top, right - left + 1, bottom - top + 1),
+        source_size: Size::new(w, h),
+    }
+}
+
+/// Trims every image, returns the inputs to pack and the [`Trim`] of each of them, in the same order.
+#[cfg(feature = "image")]
+pub fn trim_inputs<'a, K, I>(
+    images: impl IntoIterator<Item = (K, &'a I)>,
+    config: TrimConfig,
+) -> (Vec<RectInput<K>>, Vec<Trim>)
+where
+    I: GenericImageView<Pixel = Rgba<u8>> + 'a,
+{
+    images
+        .into_iter()
+        .map(|(key, image)| {
+            let trim = trim(image, config);
+            let input = RectInput {
+                size: trim.rect.size(),
+                key,
+            };
+            (input, trim)
+        })
+        .unzip()
+}
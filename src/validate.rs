@@ -0,0 +1,164 @@
+use crate::{RectInput, RectOutput, Size};
+use alloc::{vec, vec::Vec};
+
+/// Problem found by [`validate`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValidationError<K> {
+    /// Two rectangles of the same atlas overlap.
+    Overlap { atlas: usize, a: K, b: K },
+    /// Rectangle that goes outside of its atlas, or inside an atlas that doesn't exist.
+    OutOfBounds { atlas: usize, key: K },
+    /// Rectangle with a size that doesn't match its input, once flipped back.
+    SizeMismatch { key: K, expected: Size, found: Size },
+    /// Rectangle without an input with the same key.
+    UnknownKey { key: K },
+}
+
+/// Checks the result of a packing, reports every pair of overlapping rectangles, every rectangle outside
+/// of its atlas and every rectangle with a different size of its input.
+///
+/// Each output is matched to the input with the same key, `atlas_sizes` is indexed by [`RectOutput::atlas`],
+/// like [`PackResult::atlas_sizes`](crate::PackResult::atlas_sizes).
+pub fn validate<K: Copy + PartialEq>(
+    inputs: &[RectInput<K>],
+    outputs: &[RectOutput<K>],
+    atlas_sizes: &[Size],
+) -> Result<(), Vec<ValidationError<K>>> {
+    let expected: Vec<Option<Size>> = find_inputs(inputs, outputs)
+        .map(|found| found.map(|i| inputs[i].size))
+        .collect();
+
+    let errors = check(outputs, &expected, atlas_sizes);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Index of the input with the same key of each output, `None` when there isn't one.
+pub(crate) fn find_inputs<'a, K: PartialEq>(
+    inputs: &'a [RectInput<K>],
+    outputs: &'a [RectOutput<K>],
+) -> impl Iterator<Item = Option<usize>> + 'a {
+    // outputs usually keep the order of the inputs, so the search starts after the last match
+    let mut next = 0;
+    outputs.iter().map(move |output| {
+        let found = (next..inputs.len())
+            .chain(0..next)
+            .find(|&i| inputs[i].key == output.key);
+        if let Some(i) = found {
+            next = i + 1;
+        }
+        found
+    })
+}
+
+/// Validates the `outputs` against the `expected` size of each of them, `None` means no input was found.
+pub(crate) fn check<K: Copy>(
+    outputs: &[RectOutput<K>],
+    expected: &[Option<Size>],
+    atlas_sizes: &[Size],
+) -> Vec<ValidationError<K>> {
+    let mut errors = vec![];
+
+    for (output, expected) in outputs.iter().zip(expected) {
+        let rect = &output.rect;
+        let key = output.key;
+
+        match *expected {
+            None => errors.push(ValidationError::UnknownKey { key }),
+            Some(expected) => {
+                let mut found = rect.size();
+                if rect.flipped {
+                    found.flip();
+                }
+                if found != expected {
+                    errors.push(ValidationError::SizeMismatch {
+                        key,
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+
+        let inside = atlas_sizes.get(output.atlas).is_some_and(|atlas| {
+            rect.x as u64 + rect.w as u64 <= atlas.w as u64
+                && rect.y as u64 + rect.h as u64 <= atlas.h as u64
+        });
+        if !inside {
+            errors.push(ValidationError::OutOfBounds {
+                atlas: output.atlas,
+                key,
+            });
+        }
+    }
+
+    // sweep each atlas from left to right, only the rectangles that start before the end of
+    // the current one can overlap it
+    let mut order: Vec<usize> = (0..outputs.len()).collect();
+    order.sort_by_key(|&i| (outputs[i].atlas, outputs[i].rect.x));
+    for (n, &i) in order.iter().enumerate() {
+        let a = &outputs[i];
+        let right = a.rect.x as u64 + a.rect.w as u64;
+        for &j in &order[n + 1..] {
+            let b = &outputs[j];
+            if b.atlas != a.atlas || b.rect.x as u64 >= right {
+                break;
+            }
+            let overlaps = a.rect.area() > 0
+                && b.rect.area() > 0
+                && (a.rect.y as u64) < b.rect.y as u64 + b.rect.h as u64
+                && (b.rect.y as u64) < a.rect.y as u64 + a.rect.h as u64;
+            if overlaps {
+                errors.push(ValidationError::Overlap {
+                    atlas: a.atlas,
+                    a: a.key,
+                    b: b.key,
+                });
+            }
+        }
+    }
+
+    errors
+}
+
+/// Panics if the `outputs` aren't valid, used by the `debug-validate` feature.
+#[cfg(feature = "debug-validate")]
+pub(crate) fn assert_valid<K: Copy>(
+    outputs: &[RectOutput<K>],
+    expected: &[Option<Size>],
+    atlas_sizes: &[Size],
+) {
+    let errors = check(outputs, expected, atlas_sizes);
+    assert!(
+        errors.is_empty(),
+        "packing validation found {} errors, the first one is {:?}",
+        errors.len(),
+        errors.first().map(ValidationError::without_key)
+    );
+}
+
+#[cfg(feature = "debug-validate")]
+impl<K> ValidationError<K> {
+    /// Same error with the keys removed, so it can be printed.
+    fn without_key(&self) -> ValidationError<()> {
+        match *self {
+            Self::Overlap { atlas, .. } => ValidationError::Overlap {
+                atlas,
+                a: (),
+                b: (),
+            },
+            Self::OutOfBounds { atlas, .. } => ValidationError::OutOfBounds { atlas, key: () },
+            Self::SizeMismatch {
+                expected, found, ..
+            } => ValidationError::SizeMismatch {
+                key: (),
+                expected,
+                found,
+            },
+            Self::UnknownKey { .. } => ValidationError::UnknownKey { key: () },
+        }
+    }
+}
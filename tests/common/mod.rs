@@ -0,0 +1,239 @@
+#![allow(dead_code)]
+
+use packr2::*;
+use proptest::prelude::*;
+
+pub fn config() -> impl Strategy<Value = PackerConfig> {
+    (
+        1..512u32,
+        1..512u32,
+        any::<bool>(),
+        0..4u32,
+        0..4u32,
+        0..3u32,
+    )
+        .prop_map(
+            |(max_width, max_height, allow_flipping, padding, border, extrude)| PackerConfig {
+                max_width,
+                max_height,
+                allow_flipping,
+                padding,
+                border,
+                extrude,
+            },
+        )
+}
+
+pub fn sizes(max: usize) -> impl Strategy<Value = Vec<Size>> {
+    // mostly small sizes, so many of them fit, and a few close to `u32::MAX` to check the overflows
+    let side = prop_oneof![8 => 0..64u32, 1 => any::<u32>(), 1 => u32::MAX - 8..=u32::MAX];
+    prop::collection::vec(
+        (side.clone(), side).prop_map(|(w, h)| Size::new(w, h)),
+        0..max,
+    )
+}
+
+pub const SKYLINE_HEURISTICS: [SkylineHeuristic; 2] =
+    [SkylineHeuristic::BottomLeft, SkylineHeuristic::MinWaste];
+
+pub const FREE_RECT_CHOICES: [FreeRectChoice; 6] = [
+    FreeRectChoice::BestAreaFit,
+    FreeRectChoice::BestShortSideFit,
+    FreeRectChoice::BestLongSideFit,
+    FreeRectChoice::WorstAreaFit,
+    FreeRectChoice::WorstShortSideFit,
+    FreeRectChoice::WorstLongSideFit,
+];
+
+pub const SPLIT_RULES: [SplitRule; 6] = [
+    SplitRule::ShorterLeftoverAxis,
+    SplitRule::LongerLeftoverAxis,
+    SplitRule::MinimizeArea,
+    SplitRule::MaximizeArea,
+    SplitRule::ShorterAxis,
+    SplitRule::LongerAxis,
+];
+
+pub const MAX_RECTS_HEURISTICS: [MaxRectsHeuristic; 5] = [
+    MaxRectsHeuristic::BestShortSideFit,
+    MaxRectsHeuristic::BestLongSideFit,
+    MaxRectsHeuristic::BestAreaFit,
+    MaxRectsHeuristic::BottomLeft,
+    MaxRectsHeuristic::ContactPoint,
+];
+
+pub const SHELF_CHOICES: [ShelfChoice; 6] = [
+    ShelfChoice::NextFit,
+    ShelfChoice::FirstFit,
+    ShelfChoice::BestWidthFit,
+    ShelfChoice::BestHeightFit,
+    ShelfChoice::BestAreaFit,
+    ShelfChoice::WorstWidthFit,
+];
+
+/// Every packer with each of its options, the name is used in the failure messages.
+pub fn packers(config: PackerConfig) -> Vec<(String, Box<dyn DynPacker>)> {
+    let mut packers: Vec<(String, Box<dyn DynPacker>)> = vec![
+        ("strip".to_string(), Box::new(StripPacker::new(config))),
+        ("split".to_string(), Box::new(SplitPacker::new(config))),
+        (
+            "bucketed shelf".to_string(),
+            Box::new(BucketedShelfAllocator::new(config)),
+        ),
+        (
+            "bucketed shelf columns".to_string(),
+            Box::new(
+                BucketedShelfAllocator::new(config)
+                    .with_columns(3)
+                    .with_buckets_per_shelf(2),
+            ),
+        ),
+        ("buddy".to_string(), Box::new(BuddyPacker::new(config))),
+    ];
+    for heuristic in SKYLINE_HEURISTICS {
+        for waste_map in [false, true] {
+            packers.push((
+                format!("skyline {heuristic:?} waste map {waste_map}"),
+                Box::new(
+                    SkylinePacker::new(config)
+                        .with_heuristic(heuristic)
+                        .with_waste_map(waste_map),
+                ),
+            ));
+        }
+    }
+    // every choice with the default split, then every split with the default choice
+    for choice in FREE_RECT_CHOICES {
+        packers.push((
+            format!("guillotine {choice:?}"),
+            Box::new(GuillotinePacker::new(config).with_choice(choice)),
+        ));
+    }
+    for split in SPLIT_RULES {
+        packers.push((
+            format!("guillotine {split:?} merge"),
+            Box::new(
+                GuillotinePacker::new(config)
+                    .with_split(split)
+                    .with_merge(true),
+            ),
+        ));
+    }
+    for heuristic in MAX_RECTS_HEURISTICS {
+        packers.push((
+            format!("maxrects {heuristic:?}"),
+            Box::new(MaxRectsPacker::new(config).with_heuristic(heuristic)),
+        ));
+    }
+    for choice in SHELF_CHOICES {
+        packers.push((
+            format!("shelf {choice:?}"),
+            Box::new(ShelfPacker::new(config).with_choice(choice)),
+        ));
+    }
+    packers
+}
+
+/// Object safe [`Packer`], [`DynamicPacker::remove`] returns `false` when not supported.
+pub trait DynPacker {
+    fn insert(&mut self, w: u32, h: u32) -> Option<Rectf>;
+    fn reset(&mut self);
+    fn grow(&mut self, size: Size);
+    fn used_area(&self) -> Size;
+    fn remove(&mut self, rect: &Rectf) -> bool;
+}
+
+macro_rules! dyn_packer {
+    ($($packer:ty => $remove:ident),* $(,)?) => {
+        $(impl DynPacker for $packer {
+            fn insert(&mut self, w: u32, h: u32) -> Option<Rectf> {
+                Packer::insert(self, w, h)
+            }
+            fn reset(&mut self) {
+                Packer::reset(self, None)
+            }
+            fn grow(&mut self, size: Size) {
+                Packer::grow(self, size)
+            }
+            fn used_area(&self) -> Size {
+                Packer::used_area(self)
+            }
+            fn remove(&mut self, rect: &Rectf) -> bool {
+                dyn_packer!(@remove $remove self rect)
+            }
+        })*
+    };
+    (@remove yes $self:ident $rect:ident) => {{
+        DynamicPacker::remove($self, $rect);
+        true
+    }};
+    (@remove no $self:ident $rect:ident) => {{
+        let _ = $rect;
+        false
+    }};
+}
+
+dyn_packer! {
+    StripPacker => no,
+    SkylinePacker => yes,
+    SplitPacker => yes,
+    GuillotinePacker => yes,
+    MaxRectsPacker => yes,
+    ShelfPacker => no,
+    BucketedShelfAllocator => yes,
+    BuddyPacker => yes,
+}
+
+/// Checks the rectangles placed inside a single atlas of `size`, `placed` holds the input size of
+/// each of them.
+pub fn check_atlas(
+    name: &str,
+    config: &PackerConfig,
+    size: Size,
+    placed: &[(Size, Rectf)],
+) -> Result<(), TestCaseError> {
+    let margin = config.border + config.extrude;
+    let gap = config.padding + 2 * config.extrude;
+
+    for (i, (input, rect)) in placed.iter().enumerate() {
+        prop_assert!(
+            !rect.flipped || config.allow_flipping,
+            "{name}: {rect:?} flipped without allow_flipping"
+        );
+        let expected = if rect.flipped {
+            (input.h, input.w)
+        } else {
+            (input.w, input.h)
+        };
+        prop_assert_eq!((rect.w, rect.h), expected, "{}: {:?} size", name, rect);
+
+        prop_assert!(
+            rect.x >= margin
+                && rect.y >= margin
+                && rect.x + rect.w + margin <= size.w
+                && rect.y + rect.h + margin <= size.h,
+            "{name}: {rect:?} out of the {size:?} atlas"
+        );
+
+        for (_, other) in &placed[i + 1..] {
+            let overlaps = rect.x < other.x + other.w + gap
+                && other.x < rect.x + rect.w + gap
+                && rect.y < other.y + other.h + gap
+                && other.y < rect.y + rect.h + gap;
+            prop_assert!(!overlaps, "{name}: {rect:?} overlaps {other:?}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Size that [`Packer::used_area`] must report for the rectangles `placed`.
+pub fn expected_used_area(config: &PackerConfig, placed: &[(Size, Rectf)]) -> Size {
+    let mut used = Size::ZERO;
+    for (_, rect) in placed {
+        let margin = config.extrude + config.border;
+        used.w = used.w.max(rect.x + rect.w + margin);
+        used.h = used.h.max(rect.y + rect.h + margin);
+    }
+    used
+}
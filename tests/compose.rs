@@ -0,0 +1,247 @@
+#![cfg(feature = "image")]
+
+use packr2::image::{Rgba, RgbaImage};
+use packr2::*;
+
+/// Image with a different color on each pixel, so any misplaced pixel is found.
+fn source(key: u8, w: u32, h: u32) -> RgbaImage {
+    RgbaImage::from_fn(w, h, |x, y| Rgba([key, x as u8, y as u8, 255]))
+}
+
+#[test]
+fn compose_rotates_and_extrudes() {
+    let config = PackerConfig {
+        max_width: 64,
+        max_height: 64,
+        allow_flipping: true,
+        padding: 1,
+        border: 1,
+        extrude: 2,
+    };
+    let sizes = [(20, 4), (3, 30), (7, 7), (40, 2), (1, 12), (9, 5)];
+    let sources: Vec<RgbaImage> = sizes
+        .iter()
+        .enumerate()
+        .map(|(key, &(w, h))| source(key as u8, w, h))
+        .collect();
+    let inputs: Vec<RectInput<usize>> = sizes
+        .iter()
+        .enumerate()
+        .map(|(key, &(w, h))| RectInput {
+            size: Size::new(w, h),
+            key,
+        })
+        .collect();
+
+    let result = pack(&inputs, SkylinePacker::new(config));
+    assert!(result.is_complete());
+    let atlases = compose(
+        &result.rects,
+        &result.atlas_sizes,
+        &config,
+        ComposeConfig::default(),
+        |key| sources.get(key),
+    )
+    .unwrap();
+
+    let e = config.extrude as i64;
+    for output in &result.rects {
+        let rect = output.rect;
+        let source = &sources[output.key];
+        let atlas = &atlases[output.atlas];
+        for dy in -e..rect.h as i64 + e {
+            for dx in -e..rect.w as i64 + e {
+                let cx = dx.clamp(0, rect.w as i64 - 1) as u32;
+                let cy = dy.clamp(0, rect.h as i64 - 1) as u32;
+                // rotated 90 degrees clockwise: the bottom left corner of the source ends up at the top left
+                let expected = if rect.flipped {
+                    *source.get_pixel(cy, source.height() - 1 - cx)
+                } else {
+                    *source.get_pixel(cx, cy)
+                };
+                let found =
+                    atlas.get_pixel((rect.x as i64 + dx) as u32, (rect.y as i64 + dy) as u32);
+                assert_eq!(*found, expected, "{rect:?} at ({dx}, {dy})");
+            }
+        }
+    }
+
+    // the border is left with the background
+    for atlas in &atlases {
+        assert!((0..atlas.width()).all(|x| atlas.get_pixel(x, 0).0 == [0; 4]));
+        assert!((0..atlas.height()).all(|y| atlas.get_pixel(0, y).0 == [0; 4]));
+    }
+}
+
+#[test]
+fn compose_errors() {
+    let config = PackerConfig::default();
+    let rect = Rectf::from_rect(Rect::new(0, 0, 2, 3), true);
+    let outputs = [RectOutput {
+        rect,
+        atlas: 0,
+        key: 0,
+    }];
+    let atlas_sizes = [Size::new(4, 4)];
+    let image = source(0, 2, 3);
+
+    let error = compose(
+        &outputs,
+        &atlas_sizes,
+        &config,
+        ComposeConfig::default(),
+        |_| Some(&image),
+    );
+    assert_eq!(
+        error.unwrap_err(),
+        ComposeError::SizeMismatch {
+            key: 0,
+            expected: Size::new(3, 2),
+            found: Size::new(2, 3),
+        }
+    );
+
+    let error = compose(&outputs, &[], &config, ComposeConfig::default(), |_| {
+        Some(&image)
+    });
+    assert_eq!(
+        error.unwrap_err(),
+        ComposeError::UnknownAtlas { key: 0, atlas: 0 }
+    );
+
+    let error = compose::<_, RgbaImage, _>(
+        &outputs,
+        &atlas_sizes,
+        &config,
+        ComposeConfig::default(),
+        |_| None,
+    );
+    assert_eq!(error.unwrap_err(), ComposeError::MissingImage { key: 0 });
+}
+
+/// Image of `size` where only the pixels inside `visible` are opaque, the others have an alpha of `margin`.
+fn sprite(size: Size, visible: Rect, margin: u8) -> RgbaImage {
+    RgbaImage::from_fn(size.w, size.h, |x, y| {
+        let inside = x >= visible.x
+            && y >= visible.y
+            && x < visible.x + visible.w
+            && y < visible.y + visible.h;
+        let alpha = if inside { 255 } else { margin };
+        Rgba([x as u8, y as u8, 0, alpha])
+    })
+}
+
+#[test]
+fn trim_margins() {
+    let size = Size::new(16, 12);
+    let visible = Rect::new(3, 2, 5, 7);
+    let image = sprite(size, visible, 0);
+
+    let trimmed = trim(&image, TrimConfig::default());
+    assert_eq!(
+        trimmed,
+        Trim {
+            rect: visible,
+            source_size: size
+        }
+    );
+    assert!(trimmed.is_trimmed());
+
+    // the margin is only transparent up to the threshold
+    let image = sprite(size, visible, 10);
+    assert_eq!(trim(&image, TrimConfig::default()), Trim::none(size));
+    let trimmed = trim(
+        &image,
+        TrimConfig {
+            alpha_threshold: 10,
+        },
+    );
+    assert_eq!(trimmed.rect, visible);
+
+    // fully transparent images keep a single pixel
+    let image = sprite(size, Rect::new(0, 0, 0, 0), 0);
+    let trimmed = trim(&image, TrimConfig::default());
+    assert_eq!(trimmed.rect, Rect::new(0, 0, 1, 1));
+}
+
+#[test]
+fn compose_trimmed_sprites() {
+    let config = PackerConfig {
+        max_width: 64,
+        max_height: 64,
+        allow_flipping: true,
+        padding: 0,
+        border: 0,
+        extrude: 1,
+    };
+    let sprites = [
+        sprite(Size::new(20, 20), Rect::new(5, 1, 3, 16), 0),
+        sprite(Size::new(10, 30), Rect::new(0, 10, 10, 2), 0),
+        sprite(Size::new(8, 8), Rect::new(0, 0, 0, 0), 0),
+    ];
+
+    let (inputs, trims) = trim_inputs(sprites.iter().enumerate(), TrimConfig::default());
+    assert_eq!(inputs[0].size, Size::new(3, 16));
+    assert_eq!(inputs[1].size, Size::new(10, 2));
+    assert_eq!(inputs[2].size, Size::new(1, 1));
+
+    let result = pack(&inputs, MaxRectsPacker::new(config));
+    assert!(result.is_complete());
+    let atlases = compose_trimmed(
+        &result.rects,
+        &result.atlas_sizes,
+        &config,
+        ComposeConfig::default(),
+        |key| Some((&sprites[key], trims[key])),
+    )
+    .unwrap();
+
+    for output in &result.rects {
+        let rect = output.rect;
+        let trim = trims[output.key];
+        let source = &sprites[output.key];
+        for y in 0..rect.h {
+            for x in 0..rect.w {
+                let (sx, sy) = if rect.flipped {
+                    (y, trim.rect.h - 1 - x)
+                } else {
+                    (x, y)
+                };
+                let expected = source.get_pixel(trim.rect.x + sx, trim.rect.y + sy);
+                assert_eq!(
+                    atlases[output.atlas].get_pixel(rect.x + x, rect.y + y),
+                    expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn dedup_identical_images() {
+    let frames = [
+        source(0, 8, 8),
+        source(1, 8, 8),
+        source(0, 8, 8),
+        source(0, 4, 16),
+    ];
+    let inputs: Vec<RectInput<usize>> = frames
+        .iter()
+        .enumerate()
+        .map(|(key, frame)| RectInput {
+            size: Size::new(frame.width(), frame.height()),
+            key,
+        })
+        .collect();
+
+    let same =
+        |a: &RectInput<usize>, b: &RectInput<usize>| same_content(&frames[a.key], &frames[b.key]);
+    let deduped = dedup_by(&inputs, |input| content_hash(&frames[input.key]), same);
+    assert_eq!(deduped.aliases().collect::<Vec<_>>(), [(2, 0)]);
+    assert_ne!(content_hash(&frames[0]), content_hash(&frames[3]));
+
+    // every hash collides, the pixels still tell the frames apart
+    let deduped = dedup_by(&inputs, |_| 0, same);
+    assert_eq!(deduped.aliases().collect::<Vec<_>>(), [(2, 0)]);
+    assert_eq!(deduped.unique.len(), 3);
+}
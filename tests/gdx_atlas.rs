@@ -0,0 +1,266 @@
+use packr2::*;
+
+#[test]
+fn gdx_round_trip() {
+    let config = PackerConfig {
+        max_width: 64,
+        max_height: 64,
+        // libGDX turns the rotated regions the other way
+        allow_flipping: false,
+        padding: 1,
+        border: 0,
+        extrude: 0,
+    };
+    let names = ["idle", "run_0", "run_1", "run_2", "jump", "dust"];
+    let sizes = [(10, 20), (12, 6), (12, 6), (12, 6), (40, 3), (30, 30)];
+    let inputs: Vec<RectInput<&str>> = names
+        .iter()
+        .zip(sizes)
+        .map(|(&key, (w, h))| RectInput {
+            size: Size::new(w, h),
+            key,
+        })
+        .collect();
+    let trim = |key: &&str| {
+        (*key == "idle").then_some(Trim {
+            rect: Rect::new(3, 1, 10, 20),
+            source_size: Size::new(16, 32),
+        })
+    };
+
+    let result = pack(&inputs, MaxRectsPacker::new(config));
+    assert!(result.is_complete());
+    let text = to_gdx_atlas(
+        &result.rects,
+        &result.atlas_sizes,
+        &GdxConfig::default(),
+        trim,
+    )
+    .unwrap();
+    assert!(text.contains("idle\n  bounds:"));
+    assert!(text.contains("  offsets:3,11,16,32\n"));
+
+    let atlas = parse_gdx_atlas(&text).unwrap();
+    assert_eq!(atlas.atlas_sizes(), result.atlas_sizes);
+    assert_eq!(atlas.pages[0].image, "atlas0.png");
+    assert_eq!(atlas.regions.len(), result.rects.len());
+    for (region, output) in atlas.regions.iter().zip(&result.rects) {
+        assert_eq!(region.key(), output.key);
+        assert_eq!(region.page, output.atlas);
+        assert_eq!(region.rect, output.rect);
+        assert_eq!(region.degrees, 0);
+        let size = output.rect.size();
+        assert_eq!(region.trim, trim(&output.key).unwrap_or(Trim::none(size)));
+    }
+    assert_eq!(atlas.regions[2].name, "run");
+    assert_eq!(atlas.regions[2].index, 1);
+    assert!(validate(&atlas.inputs(), &atlas.outputs(), &atlas.atlas_sizes()).is_ok());
+
+    let mut flipped = result.rects.clone();
+    flipped[1].rect = Rectf::from_rect(Rect::new(0, 0, 6, 12), true);
+    assert_eq!(
+        to_gdx_atlas(&flipped, &result.atlas_sizes, &GdxConfig::default(), trim),
+        Err(GdxError::FlippedOutput { output: 1 })
+    );
+}
+
+#[test]
+fn gdx_invalid_names() {
+    let outputs = |key| {
+        [RectOutput {
+            rect: Rectf::from_rect(Rect::new(0, 0, 4, 4), false),
+            atlas: 0,
+            key,
+        }]
+    };
+    let write = |key| {
+        to_gdx_atlas(
+            &outputs(key),
+            &[Size::new(4, 4)],
+            &GdxConfig::default(),
+            |_| None,
+        )
+    };
+    for key in ["size:4", "a\nb", " a", "", "_3"] {
+        assert_eq!(
+            write(key),
+            Err(GdxError::InvalidName { output: 0 }),
+            "{key:?}"
+        );
+    }
+    assert!(write("a b_3").is_ok());
+}
+
+// as written by the libGDX TexturePacker with `prettyPrint`, the fields at their default value are left out
+const LIBGDX_ATLAS: &str = "\
+ui.png
+\tsize: 64, 64
+\tfilter: Linear, Linear
+button
+\tbounds: 2, 2, 20, 10
+button_pressed
+\tbounds: 24, 2, 20, 10
+\toffsets: 1, 1, 22, 12
+dagger
+\tbounds: 2, 14, 10, 20
+\trotate: true
+walk
+\tindex: 0
+\tbounds: 24, 14, 8, 8
+walk
+\tindex: 1
+\tbounds: 34, 14, 8, 8
+
+fonts.png
+\tsize: 32, 32
+\tpma: true
+font
+\tbounds: 0, 0, 32, 32
+";
+
+#[test]
+fn gdx_libgdx_file() {
+    // without `prettyPrint` nothing is indented and there are no spaces after the separators
+    let compact = LIBGDX_ATLAS
+        .replace('\t', "")
+        .replace(": ", ":")
+        .replace(", ", ",");
+    assert!(compact.contains("\ndagger\nbounds:2,14,10,20\n"));
+
+    for text in [LIBGDX_ATLAS, &compact] {
+        let atlas = parse_gdx_atlas(text).unwrap();
+        assert_eq!(atlas.atlas_sizes(), [Size::new(64, 64), Size::new(32, 32)]);
+        assert_eq!(atlas.pages[0].image, "ui.png");
+        assert_eq!(atlas.pages[0].filter, ["Linear", "Linear"]);
+        assert_eq!(atlas.pages[1].image, "fonts.png");
+        assert_eq!(atlas.pages[1].repeat, "none");
+
+        let keys: Vec<String> = atlas.regions.iter().map(GdxRegion::key).collect();
+        assert_eq!(
+            keys,
+            [
+                "button",
+                "button_pressed",
+                "dagger",
+                "walk_0",
+                "walk_1",
+                "font"
+            ]
+        );
+        assert_eq!(
+            atlas.regions[1].trim,
+            Trim {
+                rect: Rect::new(1, 1, 20, 10),
+                source_size: Size::new(22, 12),
+            }
+        );
+        // turned counter-clockwise, it takes 20x10 inside the page
+        assert_eq!(atlas.regions[2].degrees, 90);
+        assert_eq!(
+            atlas.regions[2].rect,
+            Rectf {
+                x: 2,
+                y: 14,
+                w: 20,
+                h: 10,
+                flipped: true,
+            }
+        );
+        assert_eq!(atlas.regions[2].trim, Trim::none(Size::new(10, 20)));
+        assert_eq!(atlas.regions[5].page, 1);
+        assert!(validate(&atlas.inputs(), &atlas.outputs(), &atlas.atlas_sizes()).is_ok());
+    }
+}
+
+// as written by the libGDX TexturePacker before 1.9.13, every page starts with a blank line
+#[test]
+fn gdx_legacy_format() {
+    let text = "
+sheet.png
+size: 64,32
+format: RGBA4444
+filter: Linear,MipMapLinearLinear
+repeat: xy
+tree
+  rotate: true
+  xy: 2, 4
+  size: 10, 20
+  orig: 14, 22
+  offset: 1, 0
+  index: -1
+
+other.png
+size: 8,8
+format: RGBA8888
+filter: Nearest,Nearest
+repeat: none
+dot
+  rotate: false
+  xy: 0, 0
+  size: 8, 8
+  orig: 8, 8
+  offset: 0, 0
+  index: 3
+";
+    let atlas = parse_gdx_atlas(text).unwrap();
+    assert_eq!(
+        atlas.pages[0],
+        GdxPage {
+            image: "sheet.png".to_string(),
+            size: Size::new(64, 32),
+            format: "RGBA4444".to_string(),
+            filter: ["Linear".to_string(), "MipMapLinearLinear".to_string()],
+            repeat: "xy".to_string(),
+        }
+    );
+    assert_eq!(
+        atlas.regions[0],
+        GdxRegion {
+            name: "tree".to_string(),
+            index: -1,
+            page: 0,
+            rect: Rectf {
+                x: 2,
+                y: 4,
+                w: 20,
+                h: 10,
+                flipped: true,
+            },
+            degrees: 90,
+            trim: Trim {
+                rect: Rect::new(1, 2, 10, 20),
+                source_size: Size::new(14, 22),
+            },
+        }
+    );
+    assert_eq!(atlas.regions[1].page, 1);
+    assert_eq!(atlas.regions[1].degrees, 0);
+    assert_eq!(atlas.regions[1].key(), "dot_3");
+}
+
+#[test]
+fn gdx_errors() {
+    let page = "atlas.png\nsize:8,8\n";
+    assert_eq!(
+        parse_gdx_atlas(&format!("{page}a\n  bounds:0,0,x,1\n")),
+        Err(GdxError::InvalidValue { line: 4 })
+    );
+    assert_eq!(
+        parse_gdx_atlas(&format!("{page}a\n  bounds:0,0,1,1\n  rotate:180\n")),
+        Err(GdxError::UnsupportedRotation { line: 5 })
+    );
+    assert_eq!(
+        parse_gdx_atlas(&format!("{page}a\n  index:1\nb\n  bounds:0,0,1,1\n")),
+        Err(GdxError::MissingBounds { line: 3 })
+    );
+    for (rotate, degrees) in [("true", 90), ("90", 90), ("270", 270), ("0", 0)] {
+        let atlas =
+            parse_gdx_atlas(&format!("{page}a\nbounds:0,0,1,2\nrotate:{rotate}\n")).unwrap();
+        assert_eq!(atlas.regions[0].degrees, degrees);
+        assert_eq!(atlas.regions[0].rect.flipped, degrees != 0);
+    }
+    assert_eq!(
+        parse_gdx_atlas(&format!("{page}\nsize:8,8\n")),
+        Err(GdxError::Syntax { line: 4 })
+    );
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "json")]
+
+use packr2::*;
+use serde_json::{json, Value};
+
+fn outputs() -> Vec<RectOutput<&'static str>> {
+    vec![
+        RectOutput {
+            rect: Rectf::from_rect(Rect::new(1, 2, 10, 20), false),
+            atlas: 0,
+            key: "idle.png",
+        },
+        RectOutput {
+            rect: Rectf::from_rect(Rect::new(11, 2, 30, 5), true),
+            atlas: 0,
+            key: "run.png",
+        },
+        RectOutput {
+            rect: Rectf::from_rect(Rect::new(0, 0, 4, 4), false),
+            atlas: 1,
+            key: "dot.png",
+        },
+    ]
+}
+
+#[test]
+fn json_hash() {
+    let atlas_sizes = [Size::new(64, 32), Size::new(4, 4)];
+    let trim = |key: &&str| {
+        (*key == "idle.png").then_some(Trim {
+            rect: Rect::new(3, 5, 10, 20),
+            source_size: Size::new(16, 32),
+        })
+    };
+    let pages =
+        to_texture_packer_json(&outputs(), &atlas_sizes, &JsonConfig::default(), trim).unwrap();
+    assert_eq!(pages.len(), 2);
+
+    let page: Value = serde_json::from_str(&pages[0]).unwrap();
+    assert_eq!(
+        page["frames"]["idle.png"],
+        json!({
+            "frame": { "x": 1, "y": 2, "w": 10, "h": 20 },
+            "rotated": false,
+            "trimmed": true,
+            "spriteSourceSize": { "x": 3, "y": 5, "w": 10, "h": 20 },
+            "sourceSize": { "w": 16, "h": 32 },
+        })
+    );
+    // the frame of rotated sprites has the size before the rotation
+    assert_eq!(
+        page["frames"]["run.png"],
+        json!({
+            "frame": { "x": 11, "y": 2, "w": 5, "h": 30 },
+            "rotated": true,
+            "trimmed": false,
+            "spriteSourceSize": { "x": 0, "y": 0, "w": 5, "h": 30 },
+            "sourceSize": { "w": 5, "h": 30 },
+        })
+    );
+    assert!(page["frames"].get("dot.png").is_none());
+    assert_eq!(page["meta"]["image"], "atlas0.png");
+    assert_eq!(page["meta"]["size"], json!({ "w": 64, "h": 32 }));
+
+    let page: Value = serde_json::from_str(&pages[1]).unwrap();
+    assert_eq!(page["meta"]["image"], "atlas1.png");
+    assert_eq!(
+        page["frames"]["dot.png"]["frame"],
+        json!({ "x": 0, "y": 0, "w": 4, "h": 4 })
+    );
+}
+
+#[test]
+fn json_array() {
+    let config = JsonConfig {
+        format: JsonFormat::Array,
+        image: "sheet-{n}.png".to_string(),
+        ..JsonConfig::default()
+    };
+    let pages =
+        to_texture_packer_json(&outputs(), &[Size::new(64, 32)], &config, |_| None).unwrap();
+    assert_eq!(pages.len(), 1);
+
+    let page: Value = serde_json::from_str(&pages[0]).unwrap();
+    let frames = page["frames"].as_array().unwrap();
+    let names: Vec<&str> = frames
+        .iter()
+        .map(|frame| frame["filename"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["idle.png", "run.png"]);
+    assert_eq!(frames[1]["rotated"], true);
+    assert_eq!(page["meta"]["image"], "sheet-0.png");
+    assert_eq!(page["meta"]["format"], "RGBA8888");
+}
+
+#[test]
+fn json_duplicate_names() {
+    let mut outputs = outputs();
+    outputs[1].key = "idle.png";
+    let atlas_sizes = [Size::new(64, 32), Size::new(4, 4)];
+    assert_eq!(
+        to_texture_packer_json(&outputs, &atlas_sizes, &JsonConfig::default(), |_| None),
+        Err(JsonError::DuplicateName { output: 1 })
+    );
+
+    // each frame has its own name field in the array
+    let config = JsonConfig {
+        format: JsonFormat::Array,
+        ..JsonConfig::default()
+    };
+    assert!(to_texture_packer_json(&outputs, &atlas_sizes, &config, |_| None).is_ok());
+
+    // names only have to be unique inside their atlas
+    outputs[1].key = "dot.png";
+    assert!(
+        to_texture_packer_json(&outputs, &atlas_sizes, &JsonConfig::default(), |_| None).is_ok()
+    );
+}
@@ -0,0 +1,405 @@
+mod common;
+
+use common::{check_atlas, config, expected_used_area, sizes};
+use packr2::*;
+use proptest::prelude::*;
+
+fn inputs(sizes: &[Size]) -> Vec<RectInput<usize>> {
+    sizes
+        .iter()
+        .enumerate()
+        .map(|(key, &size)| RectInput { size, key })
+        .collect()
+}
+
+/// Checks every atlas of a [`PackResult`] and that each input is reported once.
+fn check_result(
+    name: &str,
+    config: &PackerConfig,
+    inputs: &[RectInput<usize>],
+    result: &PackResult<usize>,
+) -> Result<(), TestCaseError> {
+    let max = Size::new(config.max_width, config.max_height);
+
+    let mut keys: Vec<usize> = result.rects.iter().map(|r| r.key).collect();
+    keys.extend(&result.unfit);
+    keys.extend(&result.overflow);
+    keys.sort();
+    prop_assert_eq!(keys, (0..inputs.len()).collect::<Vec<_>>(), "{} keys", name);
+
+    // the output keeps the order of the inputs
+    prop_assert!(
+        result.rects.windows(2).all(|w| w[0].key < w[1].key),
+        "{name} order"
+    );
+
+    for (atlas, &size) in result.atlas_sizes.iter().enumerate() {
+        let placed: Vec<(Size, Rectf)> = result
+            .rects
+            .iter()
+            .filter(|r| r.atlas == atlas)
+            .map(|r| (inputs[r.key].size, r.rect))
+            .collect();
+        prop_assert!(!placed.is_empty(), "{name} empty atlas {atlas}");
+        check_atlas(name, config, max, &placed)?;
+        prop_assert_eq!(
+            size,
+            expected_used_area(config, &placed),
+            "{} atlas {} size",
+            name,
+            atlas
+        );
+    }
+    prop_assert!(
+        result
+            .rects
+            .iter()
+            .all(|r| r.atlas < result.atlas_sizes.len()),
+        "{name} atlas out of range"
+    );
+
+    if let Err(errors) = validate(inputs, &result.rects, &result.atlas_sizes) {
+        prop_assert!(false, "{name} {errors:?}");
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn pack_every_packer(config in config(), sizes in sizes(60)) {
+        let inputs = inputs(&sizes);
+        for (name, packer) in [
+            ("strip", pack(&inputs, StripPacker::new(config))),
+            ("skyline", pack(&inputs, SkylinePacker::new(config))),
+            ("split", pack(&inputs, SplitPacker::new(config))),
+            ("maxrects", pack(&inputs, MaxRectsPacker::new(config))),
+        ] {
+            check_result(name, &config, &inputs, &packer)?;
+        }
+    }
+
+    #[test]
+    fn pack_unfit(config in config(), sizes in sizes(60)) {
+        let inputs = inputs(&sizes);
+        let result = pack(&inputs, SkylinePacker::new(config));
+
+        // only the inputs that can't fit an empty atlas are left out
+        for &key in &result.unfit {
+            let size = inputs[key].size;
+            prop_assert!(SkylinePacker::new(config).insert(size.w, size.h).is_none());
+        }
+        for output in &result.rects {
+            let size = inputs[output.key].size;
+            prop_assert!(SkylinePacker::new(config).insert(size.w, size.h).is_some());
+        }
+    }
+
+    #[test]
+    fn pack_with_input_order(config in config(), sizes in sizes(60)) {
+        let inputs = inputs(&sizes);
+        let result = pack_with(&inputs, ShelfPacker::new(config), &[]);
+        check_result("pack_with", &config, &inputs, &result)?;
+    }
+
+    #[test]
+    fn pack_multi_every_choice(
+        config in config(),
+        sizes in sizes(60),
+        best_fit in any::<bool>(),
+        rebalance in any::<bool>(),
+        max_atlases in prop::option::of(1..4usize),
+    ) {
+        let inputs = inputs(&sizes);
+        let multi = MultiAtlasConfig {
+            choice: if best_fit { AtlasChoice::BestFit } else { AtlasChoice::FirstFit },
+            rebalance,
+            max_atlases,
+        };
+        let result = pack_multi(&inputs, MaxRectsPacker::new(config), multi);
+        check_result("pack_multi", &config, &inputs, &result)?;
+        if let Some(max) = max_atlases {
+            prop_assert!(result.atlas_sizes.len() <= max);
+        } else {
+            prop_assert!(result.overflow.is_empty());
+        }
+    }
+
+    #[test]
+    fn best_size(config in config(), sizes in sizes(30)) {
+        let inputs = inputs(&sizes);
+        let max = Size::new(config.max_width, config.max_height);
+        let result = pack_with_best_size(&inputs, SkylinePacker::new(config), max);
+        check_result("best size", &config, &inputs, &result)?;
+        prop_assert!(result.atlas_sizes.len() <= 1);
+
+        // only the inputs that can't fit an empty atlas are unfit
+        for &key in &result.unfit {
+            let size = inputs[key].size;
+            prop_assert!(SkylinePacker::new(config).insert(size.w, size.h).is_none());
+        }
+        for &key in &result.overflow {
+            let size = inputs[key].size;
+            prop_assert!(SkylinePacker::new(config).insert(size.w, size.h).is_some());
+        }
+    }
+
+    #[test]
+    fn sort_functions_are_total_orders(sizes in sizes(60)) {
+        for cmp in RECT_SORT_FUNCTIONS {
+            for &a in &sizes {
+                prop_assert_eq!(cmp(a, a), core::cmp::Ordering::Equal);
+                for &b in &sizes {
+                    prop_assert_eq!(cmp(a, b), cmp(b, a).reverse());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dedup_aliases(config in config(), sizes in sizes(40), contents in prop::collection::vec(0..4u8, 40)) {
+        // few sizes and contents, so there are many duplicates
+        let sizes: Vec<Size> = sizes.iter().map(|s| Size::new(s.w % 4 + 1, s.h % 4 + 1)).collect();
+        let inputs = inputs(&sizes);
+        let deduped = dedup(&inputs, |input| contents[input.key]);
+        prop_assert_eq!(deduped.unique.len() + deduped.alias_count(), inputs.len());
+
+        let result = pack_multi(&deduped.unique, SkylinePacker::new(config), MultiAtlasConfig { max_atlases: Some(1), ..Default::default() });
+        prop_assert!(validate(&deduped.unique, &result.rects, &result.atlas_sizes).is_ok());
+
+        let expanded = deduped.expand(&result);
+        prop_assert_eq!(expanded.rects.len() + expanded.unfit.len() + expanded.overflow.len(), inputs.len());
+        prop_assert!(expanded.rects.windows(2).all(|w| w[0].key < w[1].key));
+
+        // each input shares the rectangle of the first one with the same content
+        for output in &expanded.rects {
+            let first = inputs
+                .iter()
+                .position(|i| i.size == inputs[output.key].size && contents[i.key] == contents[output.key])
+                .unwrap();
+            let packed = result.rects.iter().find(|r| r.key == first).unwrap();
+            prop_assert_eq!((output.rect.x, output.rect.y, output.atlas), (packed.rect.x, packed.rect.y, packed.atlas));
+        }
+        for (alias, key) in deduped.aliases() {
+            prop_assert!(key < alias);
+            prop_assert_eq!(
+                expanded.overflow.contains(&alias) || expanded.unfit.contains(&alias),
+                result.overflow.contains(&key) || result.unfit.contains(&key)
+            );
+        }
+    }
+}
+
+#[test]
+fn unfit_keeps_the_atlas_open() {
+    let config = PackerConfig {
+        max_width: 100,
+        max_height: 100,
+        ..PackerConfig::default()
+    };
+    let inputs = inputs(&[Size::new(10, 10), Size::new(500, 500), Size::new(10, 10)]);
+    let result = pack_with(&inputs, SkylinePacker::new(config), &[]);
+    assert_eq!(result.unfit, [1]);
+    assert_eq!(result.atlas_sizes.len(), 1);
+    assert!(result.rects.iter().all(|r| r.atlas == 0));
+}
+
+#[test]
+fn empty_inputs_are_unfit() {
+    let inputs = inputs(&[Size::new(0, 5), Size::new(4, 4), Size::new(5, 0)]);
+    let result = pack(&inputs, SkylinePacker::new(PackerConfig::default()));
+    assert_eq!(result.unfit, [0, 2]);
+    assert_eq!(result.rects.len(), 1);
+}
+
+/// Difference between the fullest and the emptiest atlas.
+fn fill_spread(inputs: &[RectInput<usize>], result: &PackResult<usize>) -> u64 {
+    let mut fill = vec![0; result.atlas_sizes.len()];
+    for output in &result.rects {
+        fill[output.atlas] += inputs[output.key].size.area();
+    }
+    fill.iter().max().unwrap_or(&0) - fill.iter().min().unwrap_or(&0)
+}
+
+proptest! {
+    #[test]
+    fn rebalance_never_worse(config in config(), sizes in sizes(60)) {
+        let inputs = inputs(&sizes);
+        let greedy = pack_multi(&inputs, ShelfPacker::new(config), MultiAtlasConfig::default());
+        let rebalanced = pack_multi(
+            &inputs,
+            ShelfPacker::new(config),
+            MultiAtlasConfig { rebalance: true, ..Default::default() },
+        );
+        check_result("rebalance", &config, &inputs, &rebalanced)?;
+        prop_assert_eq!(rebalanced.atlas_sizes.len(), greedy.atlas_sizes.len());
+        prop_assert!(fill_spread(&inputs, &rebalanced) <= fill_spread(&inputs, &greedy));
+    }
+}
+
+/// Key, rectangle and atlas of every output, to compare two results.
+fn placements(result: &PackResult<usize>) -> Vec<(usize, Rectf, usize)> {
+    result
+        .rects
+        .iter()
+        .map(|r| (r.key, r.rect, r.atlas))
+        .collect()
+}
+
+/// Number of inputs left out and total area of the atlases, lower is better.
+fn score(result: &PackResult<usize>) -> (usize, u64) {
+    let area = result.atlas_sizes.iter().map(Size::area).sum();
+    (result.unfit.len(), area)
+}
+
+proptest! {
+    #[test]
+    fn annealing(config in config(), sizes in sizes(30), seed in any::<u64>()) {
+        let inputs = inputs(&sizes);
+        let anneal = AnnealConfig {
+            iterations: 100,
+            seed,
+            rotate: config.allow_flipping,
+            time_limit: None,
+        };
+        let result = pack_annealing(&inputs, SkylinePacker::new(config), anneal);
+        check_result("annealing", &config, &inputs, &result)?;
+        prop_assert!(score(&result) <= score(&pack(&inputs, SkylinePacker::new(config))));
+
+        // the same seed gives the same packing
+        let again = pack_annealing(&inputs, SkylinePacker::new(config), anneal);
+        prop_assert_eq!(placements(&again), placements(&result));
+        prop_assert_eq!(again.atlas_sizes, result.atlas_sizes);
+    }
+}
+
+#[cfg(feature = "rayon")]
+proptest! {
+    #[test]
+    fn parallel_same_result(config in config(), sizes in sizes(40)) {
+        let inputs = inputs(&sizes);
+        let max = Size::new(config.max_width, config.max_height);
+        for (sequential, parallel) in [
+            (pack(&inputs, MaxRectsPacker::new(config)), par_pack(&inputs, MaxRectsPacker::new(config))),
+            (
+                pack_with_best_size(&inputs, SkylinePacker::new(config), max),
+                par_pack_with_best_size(&inputs, SkylinePacker::new(config), max),
+            ),
+        ] {
+            prop_assert_eq!(placements(&parallel), placements(&sequential));
+            prop_assert_eq!(parallel.atlas_sizes, sequential.atlas_sizes);
+            prop_assert_eq!(parallel.unfit, sequential.unfit);
+            prop_assert_eq!(parallel.overflow, sequential.overflow);
+        }
+    }
+}
+
+/// Whether the rectangles not `placed` fit the free cells of `grid` from `cell` on, each free cell is either
+/// the top-left corner of a rectangle or left empty.
+fn fill(
+    grid: &mut [bool],
+    w: usize,
+    sizes: &[Size],
+    placed: &mut [bool],
+    flipping: bool,
+    cell: usize,
+) -> bool {
+    if placed.iter().all(|&p| p) {
+        return true;
+    }
+    let Some(cell) = (cell..grid.len()).find(|&c| !grid[c]) else {
+        return false;
+    };
+    let free = grid[cell..].iter().filter(|&&used| !used).count() as u64;
+    let left: u64 = sizes
+        .iter()
+        .zip(&*placed)
+        .filter(|(_, &p)| !p)
+        .map(|(size, _)| size.area())
+        .sum();
+    if free < left {
+        return false;
+    }
+
+    let (x, y, h) = (cell % w, cell / w, grid.len() / w);
+    for i in 0..sizes.len() {
+        if placed[i] {
+            continue;
+        }
+        let Size { w: rw, h: rh } = sizes[i];
+        let sides = if flipping { 2 } else { 1 };
+        for (rw, rh) in [(rw as usize, rh as usize), (rh as usize, rw as usize)]
+            .into_iter()
+            .take(sides)
+        {
+            let cells = || (y..y + rh).flat_map(|cy| (x..x + rw).map(move |cx| cy * w + cx));
+            if x + rw > w || y + rh > h || cells().any(|c| grid[c]) {
+                continue;
+            }
+            cells().for_each(|c| grid[c] = true);
+            placed[i] = true;
+            let found = fill(grid, w, sizes, placed, flipping, cell + 1);
+            cells().for_each(|c| grid[c] = false);
+            placed[i] = false;
+            if found {
+                return true;
+            }
+        }
+    }
+
+    grid[cell] = true;
+    let found = fill(grid, w, sizes, placed, flipping, cell + 1);
+    grid[cell] = false;
+    found
+}
+
+/// Smallest atlas area that holds the rectangles, trying every atlas size from the smallest area up.
+fn brute_force_area(sizes: &[Size], flipping: bool) -> u64 {
+    let total: u64 = sizes.iter().map(Size::area).sum();
+    (total..)
+        .find(|&area| {
+            (1..=area).filter(|w| area % w == 0).any(|w| {
+                let mut grid = vec![false; area as usize];
+                let mut placed = vec![false; sizes.len()];
+                fill(&mut grid, w as usize, sizes, &mut placed, flipping, 0)
+            })
+        })
+        .unwrap()
+}
+
+proptest! {
+    #[test]
+    fn optimal_never_worse(config in config(), sizes in sizes(10)) {
+        let inputs = inputs(&sizes);
+        let limits = OptimalConfig { max_nodes: 100_000, ..Default::default() };
+        let result = pack_optimal(&inputs, config, limits);
+
+        let mut keys: Vec<usize> = result.rects.iter().map(|r| r.key).collect();
+        keys.extend(&result.unfit);
+        keys.sort();
+        prop_assert_eq!(keys, (0..inputs.len()).collect::<Vec<_>>());
+        if let Err(errors) = validate(&inputs, &result.rects, &[result.size]) {
+            prop_assert!(false, "{errors:?}");
+        }
+        let placed: Vec<(Size, Rectf)> = result.rects.iter().map(|r| (inputs[r.key].size, r.rect)).collect();
+        prop_assert_eq!(result.size, expected_used_area(&config, &placed));
+
+        let max = Size::new(config.max_width, config.max_height);
+        let heuristic = pack_with_best_size(&inputs, MaxRectsPacker::new(config), max);
+        if heuristic.is_complete() {
+            prop_assert!(result.unfit.is_empty());
+            prop_assert!(result.size.area() <= heuristic.atlas_sizes.first().map_or(0, Size::area));
+        }
+    }
+
+    #[test]
+    fn optimal_brute_force(
+        allow_flipping in any::<bool>(),
+        sizes in prop::collection::vec((1..5u32, 1..5u32).prop_map(|(w, h)| Size::new(w, h)), 1..6),
+    ) {
+        let config = PackerConfig { max_width: 64, max_height: 64, allow_flipping, ..Default::default() };
+        let result = pack_optimal(&inputs(&sizes), config, OptimalConfig::default());
+        prop_assert!(result.optimal);
+        prop_assert_eq!(result.size.area(), brute_force_area(&sizes, allow_flipping));
+    }
+}
@@ -0,0 +1,162 @@
+mod common;
+
+use common::*;
+use packr2::*;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn insert(config in config(), sizes in sizes(80)) {
+        let max = Size::new(config.max_width, config.max_height);
+        for (name, mut packer) in packers(config) {
+            let mut placed = vec![];
+            for &size in &sizes {
+                if let Some(rect) = packer.insert(size.w, size.h) {
+                    placed.push((size, rect));
+                }
+            }
+
+            check_atlas(&name, &config, max, &placed)?;
+            prop_assert_eq!(packer.used_area(), expected_used_area(&config, &placed), "{} used area", name);
+        }
+    }
+
+    #[test]
+    fn reset(config in config(), sizes in sizes(40)) {
+        for (name, mut packer) in packers(config) {
+            let first: Vec<_> = sizes.iter().map(|s| packer.insert(s.w, s.h).map(|r| (r.x, r.y, r.flipped))).collect();
+            packer.reset();
+            prop_assert_eq!(packer.used_area(), Size::ZERO, "{} used area after reset", name);
+            let second: Vec<_> = sizes.iter().map(|s| packer.insert(s.w, s.h).map(|r| (r.x, r.y, r.flipped))).collect();
+            prop_assert_eq!(first, second, "{} placements after reset", name);
+        }
+    }
+
+    #[test]
+    fn grow(config in config(), sizes in sizes(80), extra in (0..256u32, 0..256u32)) {
+        let grown = Size::new(config.max_width + extra.0, config.max_height + extra.1);
+        for (name, mut packer) in packers(config) {
+            let (before, after) = sizes.split_at(sizes.len() / 2);
+
+            let mut placed = vec![];
+            for &size in before {
+                if let Some(rect) = packer.insert(size.w, size.h) {
+                    placed.push((size, rect));
+                }
+            }
+            packer.grow(grown);
+            for &size in after {
+                if let Some(rect) = packer.insert(size.w, size.h) {
+                    placed.push((size, rect));
+                }
+            }
+
+            check_atlas(&name, &config, grown, &placed)?;
+        }
+    }
+
+    #[test]
+    fn remove(config in config(), sizes in sizes(80), removals in prop::collection::vec(any::<prop::sample::Index>(), 0..80)) {
+        let max = Size::new(config.max_width, config.max_height);
+        for (name, mut packer) in packers(config) {
+            let mut live: Vec<(Size, Rectf)> = vec![];
+            let mut supported = true;
+            for (i, &size) in sizes.iter().enumerate() {
+                if let Some(rect) = packer.insert(size.w, size.h) {
+                    live.push((size, rect));
+                }
+                // remove a rectangle every other insertion
+                if i % 2 == 1 && !live.is_empty() {
+                    if let Some(index) = removals.get(i / 2) {
+                        let (_, rect) = live.swap_remove(index.index(live.len()));
+                        supported = packer.remove(&rect);
+                        if !supported {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if supported {
+                check_atlas(&name, &config, max, &live)?;
+            }
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn bucketed_shelf_deallocate(sizes in sizes(80), columns in 1..4u32) {
+        let config = PackerConfig {
+            max_width: 256,
+            max_height: 256,
+            allow_flipping: false,
+            ..PackerConfig::default()
+        };
+        let mut allocator = BucketedShelfAllocator::new(config).with_columns(columns);
+        for _ in 0..2 {
+            let mut placed = vec![];
+            let mut ids = vec![];
+            for &size in &sizes {
+                if let Some(allocation) = allocator.allocate(size.w, size.h) {
+                    placed.push((size, allocation.rect));
+                    ids.push(allocation.id);
+                }
+            }
+            check_atlas("bucketed shelf", &config, Size::new(256, 256), &placed)?;
+
+            // out of order, so the empty shelves are merged with the ones above and below them
+            let (even, odd): (Vec<_>, Vec<_>) = ids.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+            for (_, &id) in even.into_iter().chain(odd.into_iter().rev()) {
+                allocator.deallocate(id);
+            }
+            prop_assert!(allocator.is_empty());
+
+            // every shelf is gone, the first column can be taken whole
+            let column = allocator.allocate(256 / columns, 256);
+            prop_assert_eq!(column.map(|allocation| allocation.rect), Some(Rectf::from_rect(Rect::new(0, 0, 256 / columns, 256), false)));
+            allocator.deallocate(column.unwrap().id);
+            prop_assert!(allocator.is_empty());
+        }
+    }
+}
+
+#[test]
+fn huge_sizes() {
+    let configs = [
+        PackerConfig {
+            padding: 1,
+            ..PackerConfig::default()
+        },
+        PackerConfig {
+            max_width: u32::MAX,
+            max_height: u32::MAX,
+            allow_flipping: true,
+            padding: 1,
+            border: 2,
+            extrude: 3,
+        },
+    ];
+    for config in configs {
+        for (name, mut packer) in packers(config) {
+            // the padding and extrusion push the slot past `u32::MAX`
+            assert_eq!(packer.insert(u32::MAX, 1), None, "{name}");
+            assert_eq!(packer.insert(1, u32::MAX), None, "{name}");
+
+            // as big as the bin allows, then smaller ones next to it
+            let mut placed = vec![];
+            for size in [
+                Size::new(u32::MAX - 16, 4),
+                Size::new(8, 8),
+                Size::new(u32::MAX / 2, u32::MAX / 2),
+                Size::new(3, 5),
+            ] {
+                if let Some(rect) = packer.insert(size.w, size.h) {
+                    placed.push((size, rect));
+                }
+            }
+            let max = Size::new(config.max_width, config.max_height);
+            check_atlas(&name, &config, max, &placed).unwrap();
+        }
+    }
+}